@@ -0,0 +1,265 @@
+/// This module contains the reading of numpy arrays through the buffer protocol,
+/// without creating a Python object per value.
+use pyo3::buffer::{Element, PyBuffer};
+use pyo3::exceptions::{PyIndexError, PyTypeError};
+use pyo3::intern;
+use pyo3::prelude::*;
+
+use crate::datetime::{DateTimeValue, DateValue};
+use crate::errors;
+use crate::number::IntValue;
+use crate::util::{MissingValue, ValueType};
+
+/// The value of `numpy.datetime64("NaT")` in its integer representation.
+const NAT: i64 = i64::MIN;
+
+/// The unit of a `datetime64` array.
+#[derive(Clone, Copy)]
+enum DateTimeUnit {
+    /// A date unit, as a number of days per value.
+    Days(i64),
+    /// A time unit, as a number of seconds per value.
+    Seconds(f64),
+}
+
+impl DateTimeUnit {
+    /// Parses the unit of a `datetime64` dtype string, such as `"<M8[ns]"` or `"<M8[15m]"`.
+    fn parse(dtype: &str) -> PyResult<DateTimeUnit> {
+        let unit = dtype
+            .split_once('[')
+            .and_then(|(_, unit)| unit.strip_suffix(']'))
+            .unwrap_or_default();
+        let digits = unit.len() - unit.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let count: i64 = unit[..digits].parse().unwrap_or(1);
+        match &unit[digits..] {
+            "W" => Ok(DateTimeUnit::Days(count * 7)),
+            "D" => Ok(DateTimeUnit::Days(count)),
+            "h" => Ok(DateTimeUnit::Seconds(count as f64 * 3600.0)),
+            "m" => Ok(DateTimeUnit::Seconds(count as f64 * 60.0)),
+            "s" => Ok(DateTimeUnit::Seconds(count as f64)),
+            "ms" => Ok(DateTimeUnit::Seconds(count as f64 / 1e3)),
+            "us" => Ok(DateTimeUnit::Seconds(count as f64 / 1e6)),
+            "ns" => Ok(DateTimeUnit::Seconds(count as f64 / 1e9)),
+            _ => Err(PyTypeError::new_err(format!(
+                "Unsupported datetime64 unit in dtype '{dtype}'. Convert the array to 'datetime64[D]' or 'datetime64[ns]' first."
+            ))),
+        }
+    }
+
+    fn value(&self, value: i64) -> PyResult<ValueType> {
+        match self {
+            DateTimeUnit::Days(days) => match value.checked_mul(*days) {
+                Some(days) => Ok(ValueType::Date(DateValue::from_unix_days(days))),
+                None => Err(errors::new_err::<errors::ParameterError>(
+                    format!("The datetime64 value {value} is out of the range of Excel dates."),
+                    None,
+                    None,
+                )),
+            },
+            DateTimeUnit::Seconds(seconds) => Ok(ValueType::DateTime(
+                DateTimeValue::from_unix_seconds(value as f64 * seconds),
+            )),
+        }
+    }
+}
+
+/// The buffer of an array, by the type of its values.
+enum ArrayData {
+    F64(PyBuffer<f64>),
+    F32(PyBuffer<f32>),
+    I64(PyBuffer<i64>),
+    I32(PyBuffer<i32>),
+    I16(PyBuffer<i16>),
+    I8(PyBuffer<i8>),
+    U64(PyBuffer<u64>),
+    U32(PyBuffer<u32>),
+    U16(PyBuffer<u16>),
+    U8(PyBuffer<u8>),
+    /// A boolean array, viewed as bytes.
+    Bool(PyBuffer<u8>),
+    /// A `datetime64` array, viewed as integers.
+    DateTime(PyBuffer<i64>, DateTimeUnit),
+}
+
+/// A 1D or 2D numpy array of numbers, booleans or `datetime64` values.
+///
+/// The values are read in place through the buffer protocol. Boolean arrays are read
+/// through a view as bytes, and `datetime64` arrays, which do not support the buffer
+/// protocol, through a view as integers. Views do not copy the array either.
+pub struct ArrayValues {
+    data: ArrayData,
+    rows: usize,
+    columns: usize,
+}
+
+impl<'py> FromPyObject<'py> for ArrayValues {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        let py = ob.py();
+        let dtype = ob.getattr(intern!(py, "dtype"))?;
+        let kind: char = dtype.getattr(intern!(py, "kind"))?.extract()?;
+        let item_size: usize = dtype.getattr(intern!(py, "itemsize"))?.extract()?;
+        let view = |dtype: &str| ob.call_method1(intern!(py, "view"), (dtype,));
+
+        let data = match (kind, item_size) {
+            ('f', 8) => ArrayData::F64(PyBuffer::get(ob)?),
+            ('f', 4) => ArrayData::F32(PyBuffer::get(ob)?),
+            ('i', 8) => ArrayData::I64(PyBuffer::get(ob)?),
+            ('i', 4) => ArrayData::I32(PyBuffer::get(ob)?),
+            ('i', 2) => ArrayData::I16(PyBuffer::get(ob)?),
+            ('i', 1) => ArrayData::I8(PyBuffer::get(ob)?),
+            ('u', 8) => ArrayData::U64(PyBuffer::get(ob)?),
+            ('u', 4) => ArrayData::U32(PyBuffer::get(ob)?),
+            ('u', 2) => ArrayData::U16(PyBuffer::get(ob)?),
+            ('u', 1) => ArrayData::U8(PyBuffer::get(ob)?),
+            ('b', 1) => ArrayData::Bool(PyBuffer::get(&view("u1")?)?),
+            ('M', 8) => {
+                let dtype: String = dtype.getattr(intern!(py, "str"))?.extract()?;
+                ArrayData::DateTime(PyBuffer::get(&view("i8")?)?, DateTimeUnit::parse(&dtype)?)
+            }
+            _ => {
+                return Err(PyTypeError::new_err(format!(
+                    "Unsupported array dtype '{}'. Expected a float, integer, bool or datetime64 array.",
+                    dtype.str()?
+                )))
+            }
+        };
+
+        let shape = match &data {
+            ArrayData::F64(buffer) => buffer.shape(),
+            ArrayData::F32(buffer) => buffer.shape(),
+            ArrayData::I64(buffer) | ArrayData::DateTime(buffer, _) => buffer.shape(),
+            ArrayData::I32(buffer) => buffer.shape(),
+            ArrayData::I16(buffer) => buffer.shape(),
+            ArrayData::I8(buffer) => buffer.shape(),
+            ArrayData::U64(buffer) => buffer.shape(),
+            ArrayData::U32(buffer) => buffer.shape(),
+            ArrayData::U16(buffer) => buffer.shape(),
+            ArrayData::U8(buffer) | ArrayData::Bool(buffer) => buffer.shape(),
+        };
+        let (rows, columns) = match *shape {
+            [rows] => (rows, 1),
+            [rows, columns] => (rows, columns),
+            _ => {
+                return Err(PyTypeError::new_err(format!(
+                    "Expected a 1D or 2D array, got an array with {} dimensions.",
+                    shape.len()
+                )))
+            }
+        };
+        Ok(ArrayValues {
+            data,
+            rows,
+            columns,
+        })
+    }
+}
+
+impl ArrayValues {
+    /// The number of rows of the array. A 1D array has one row per value.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// The number of columns of the array. A 1D array has a single column.
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// Returns the value at the given row and column of the array.
+    /// `NaT` values are missing values, while NaN values are written with the `nan_policy`.
+    pub fn value(&self, row: usize, column: usize) -> PyResult<ValueType> {
+        if row >= self.rows || column >= self.columns {
+            return Err(PyIndexError::new_err(format!(
+                "Index ({row}, {column}) is out of bounds for an array of shape ({}, {}).",
+                self.rows, self.columns
+            )));
+        }
+        Ok(match &self.data {
+            ArrayData::F64(buffer) => ValueType::Float(read(buffer, row, column)),
+            ArrayData::F32(buffer) => ValueType::Float(read(buffer, row, column).into()),
+            ArrayData::I64(buffer) => ValueType::Int(IntValue::Small(read(buffer, row, column))),
+            ArrayData::I32(buffer) => {
+                ValueType::Int(IntValue::Small(read(buffer, row, column).into()))
+            }
+            ArrayData::I16(buffer) => {
+                ValueType::Int(IntValue::Small(read(buffer, row, column).into()))
+            }
+            ArrayData::I8(buffer) => {
+                ValueType::Int(IntValue::Small(read(buffer, row, column).into()))
+            }
+            ArrayData::U64(buffer) => {
+                let value = read(buffer, row, column);
+                ValueType::Int(match i64::try_from(value) {
+                    Ok(value) => IntValue::Small(value),
+                    Err(_) => IntValue::Big(value.to_string()),
+                })
+            }
+            ArrayData::U32(buffer) => {
+                ValueType::Int(IntValue::Small(read(buffer, row, column).into()))
+            }
+            ArrayData::U16(buffer) => {
+                ValueType::Int(IntValue::Small(read(buffer, row, column).into()))
+            }
+            ArrayData::U8(buffer) => {
+                ValueType::Int(IntValue::Small(read(buffer, row, column).into()))
+            }
+            ArrayData::Bool(buffer) => ValueType::Bool(read(buffer, row, column) != 0),
+            ArrayData::DateTime(buffer, unit) => match read(buffer, row, column) {
+                NAT => ValueType::Missing(MissingValue),
+                value => unit.value(value)?,
+            },
+        })
+    }
+}
+
+/// Reads the value at the given row and column of a 1D or 2D buffer, following its strides.
+fn read<T: Element + Copy>(buffer: &PyBuffer<T>, row: usize, column: usize) -> T {
+    let strides = buffer.strides();
+    let offset =
+        row as isize * strides[0] + strides.get(1).map_or(0, |&stride| column as isize * stride);
+    // SAFETY: the row and column are within the shape of the buffer, which was checked to
+    // hold values of `T`, and the buffer is kept alive while the GIL is held.
+    unsafe {
+        buffer
+            .buf_ptr()
+            .cast::<u8>()
+            .offset(offset)
+            .cast::<T>()
+            .read_unaligned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_datetime64_units() {
+        assert!(matches!(
+            DateTimeUnit::parse("<M8[D]"),
+            Ok(DateTimeUnit::Days(1))
+        ));
+        assert!(matches!(
+            DateTimeUnit::parse("<M8[W]"),
+            Ok(DateTimeUnit::Days(7))
+        ));
+        assert!(matches!(
+            DateTimeUnit::parse("<M8[15m]"),
+            Ok(DateTimeUnit::Seconds(900.0))
+        ));
+        assert!(
+            matches!(DateTimeUnit::parse("<M8[ns]"), Ok(DateTimeUnit::Seconds(seconds)) if seconds == 1e-9)
+        );
+    }
+
+    #[test]
+    fn out_of_range_dates_raise_instead_of_overflowing() {
+        pyo3::prepare_freethreaded_python();
+        assert!(DateTimeUnit::Days(7).value(i64::MAX / 2).is_err());
+        assert!(DateTimeUnit::Days(7).value(i64::MIN + 1).is_err());
+        assert!(matches!(
+            DateTimeUnit::Days(1).value(i64::MAX),
+            Ok(ValueType::Date(_))
+        ));
+    }
+}
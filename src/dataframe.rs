@@ -0,0 +1,584 @@
+/// This module contains the reading of dataframes through the Arrow PyCapsule interface,
+/// without creating a Python object per value.
+///
+/// The structures below are those of the Arrow C Data and C Stream interfaces, see
+/// <https://arrow.apache.org/docs/format/CDataInterface.html>.
+use std::ffi::{c_char, c_int, c_void, CStr};
+use std::{mem, ptr, slice};
+
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::intern;
+use pyo3::prelude::*;
+use pyo3::types::{IntoPyDict, PyCapsule};
+
+use crate::datetime::{DateTimeValue, DateValue, TimeDeltaValue, TimeValue, TimezonePolicy};
+use crate::number::IntValue;
+use crate::util::{MissingValue, ValueType};
+
+const MILLISECONDS_PER_DAY: i64 = 86_400_000;
+
+#[repr(C)]
+struct ArrowSchema {
+    format: *const c_char,
+    name: *const c_char,
+    metadata: *const c_char,
+    flags: i64,
+    n_children: i64,
+    children: *mut *mut ArrowSchema,
+    dictionary: *mut ArrowSchema,
+    release: Option<unsafe extern "C" fn(*mut ArrowSchema)>,
+    private_data: *mut c_void,
+}
+
+#[repr(C)]
+struct ArrowArray {
+    length: i64,
+    null_count: i64,
+    offset: i64,
+    n_buffers: i64,
+    n_children: i64,
+    buffers: *mut *const c_void,
+    children: *mut *mut ArrowArray,
+    dictionary: *mut ArrowArray,
+    release: Option<unsafe extern "C" fn(*mut ArrowArray)>,
+    private_data: *mut c_void,
+}
+
+#[repr(C)]
+struct ArrowArrayStream {
+    get_schema: Option<unsafe extern "C" fn(*mut ArrowArrayStream, *mut ArrowSchema) -> c_int>,
+    get_next: Option<unsafe extern "C" fn(*mut ArrowArrayStream, *mut ArrowArray) -> c_int>,
+    get_last_error: Option<unsafe extern "C" fn(*mut ArrowArrayStream) -> *const c_char>,
+    release: Option<unsafe extern "C" fn(*mut ArrowArrayStream)>,
+    private_data: *mut c_void,
+}
+
+// The producer frees each structure, and everything it owns, through its `release`
+// callback. Children and dictionaries are released with their parent.
+impl Drop for ArrowSchema {
+    fn drop(&mut self) {
+        if let Some(release) = self.release {
+            // SAFETY: the structure has not been released yet.
+            unsafe { release(self) }
+        }
+    }
+}
+
+impl Drop for ArrowArray {
+    fn drop(&mut self) {
+        if let Some(release) = self.release {
+            // SAFETY: the structure has not been released yet.
+            unsafe { release(self) }
+        }
+    }
+}
+
+impl Drop for ArrowArrayStream {
+    fn drop(&mut self) {
+        if let Some(release) = self.release {
+            // SAFETY: the structure has not been released yet.
+            unsafe { release(self) }
+        }
+    }
+}
+
+impl ArrowArray {
+    /// Returns the buffer at `index`, which is null for an absent validity buffer.
+    fn buffer(&self, index: usize) -> *const c_void {
+        if index as i64 >= self.n_buffers {
+            return ptr::null();
+        }
+        // SAFETY: the array has `n_buffers` buffers.
+        unsafe { *self.buffers.add(index) }
+    }
+
+    /// Returns the child array at `index`.
+    fn child(&self, index: usize) -> &ArrowArray {
+        assert!((index as i64) < self.n_children);
+        // SAFETY: the array has `n_children` children, which live as long as their parent.
+        unsafe { &**self.children.add(index) }
+    }
+
+    /// Returns whether the value at `index`, including the array offset, is not null.
+    fn is_valid(&self, index: usize) -> bool {
+        let validity = self.buffer(0);
+        // SAFETY: the validity bitmap has a bit for every value of the array.
+        self.null_count == 0 || validity.is_null() || unsafe { bit(validity, index) }
+    }
+}
+
+/// The integer types of Arrow, for integer columns and dictionary indices.
+#[derive(Clone, Copy)]
+enum IntType {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+}
+
+impl IntType {
+    fn parse(format: &str) -> Option<IntType> {
+        match format {
+            "c" => Some(IntType::I8),
+            "s" => Some(IntType::I16),
+            "i" => Some(IntType::I32),
+            "l" => Some(IntType::I64),
+            "C" => Some(IntType::U8),
+            "S" => Some(IntType::U16),
+            "I" => Some(IntType::U32),
+            "L" => Some(IntType::U64),
+            _ => None,
+        }
+    }
+
+    /// Reads the integer at `index` of a buffer of this type.
+    ///
+    /// # Safety
+    /// The buffer must hold more than `index` integers of this type.
+    unsafe fn read(self, buffer: *const c_void, index: usize) -> i128 {
+        match self {
+            IntType::I8 => read::<i8>(buffer, index).into(),
+            IntType::I16 => read::<i16>(buffer, index).into(),
+            IntType::I32 => read::<i32>(buffer, index).into(),
+            IntType::I64 => read::<i64>(buffer, index).into(),
+            IntType::U8 => read::<u8>(buffer, index).into(),
+            IntType::U16 => read::<u16>(buffer, index).into(),
+            IntType::U32 => read::<u32>(buffer, index).into(),
+            IntType::U64 => read::<u64>(buffer, index).into(),
+        }
+    }
+}
+
+/// The type of a dataframe column, parsed from its Arrow format string.
+enum ColumnType {
+    Null,
+    Bool,
+    Int(IntType),
+    Float32,
+    Float64,
+    /// A UTF-8 string, with 64-bit offsets if `large`.
+    String {
+        large: bool,
+    },
+    StringView,
+    /// A number of days since the Unix epoch.
+    Date32,
+    /// A number of milliseconds since the Unix epoch.
+    Date64,
+    /// A number of `unit` seconds since the Unix epoch, `utc_offset` seconds ahead of UTC
+    /// if the column has a timezone.
+    Timestamp {
+        unit: f64,
+        utc_offset: Option<f64>,
+    },
+    /// A number of `unit` seconds since midnight, stored in 64 bits if `large`.
+    Time {
+        unit: f64,
+        large: bool,
+    },
+    /// A number of `unit` seconds.
+    Duration {
+        unit: f64,
+    },
+    Dictionary {
+        indices: IntType,
+        values: Box<ColumnType>,
+    },
+}
+
+impl ColumnType {
+    /// Parses the type of a column, or of the values of a dictionary column.
+    ///
+    /// # Safety
+    /// The schema must be valid, as exported by an Arrow producer.
+    unsafe fn parse(schema: &ArrowSchema, tz_policy: TimezonePolicy) -> PyResult<ColumnType> {
+        let format = CStr::from_ptr(schema.format).to_string_lossy();
+        if !schema.dictionary.is_null() {
+            let indices = IntType::parse(&format).ok_or_else(|| unsupported(schema, &format))?;
+            let values = ColumnType::parse(&*schema.dictionary, tz_policy)?;
+            return Ok(ColumnType::Dictionary {
+                indices,
+                values: Box::new(values),
+            });
+        }
+        if let Some(int_type) = IntType::parse(&format) {
+            return Ok(ColumnType::Int(int_type));
+        }
+        let column_type = match &*format {
+            "n" => ColumnType::Null,
+            "b" => ColumnType::Bool,
+            "f" => ColumnType::Float32,
+            "g" => ColumnType::Float64,
+            "u" => ColumnType::String { large: false },
+            "U" => ColumnType::String { large: true },
+            "vu" => ColumnType::StringView,
+            "tdD" => ColumnType::Date32,
+            "tdm" => ColumnType::Date64,
+            "tts" => ColumnType::Time {
+                unit: 1.0,
+                large: false,
+            },
+            "ttm" => ColumnType::Time {
+                unit: 1e-3,
+                large: false,
+            },
+            "ttu" => ColumnType::Time {
+                unit: 1e-6,
+                large: true,
+            },
+            "ttn" => ColumnType::Time {
+                unit: 1e-9,
+                large: true,
+            },
+            _ => {
+                let (unit, timezone) = match format
+                    .strip_prefix("ts")
+                    .filter(|ts| !ts.is_empty())
+                    .map(|ts| ts.split_at(1))
+                {
+                    Some((unit, timezone)) => (unit, timezone.strip_prefix(':')),
+                    None => match format.strip_prefix("tD") {
+                        Some(unit) => (unit, None),
+                        None => return Err(unsupported(schema, &format)),
+                    },
+                };
+                let unit = match unit {
+                    "s" => 1.0,
+                    "m" => 1e-3,
+                    "u" => 1e-6,
+                    "n" => 1e-9,
+                    _ => return Err(unsupported(schema, &format)),
+                };
+                match timezone {
+                    None => ColumnType::Duration { unit },
+                    Some(timezone) => ColumnType::Timestamp {
+                        unit,
+                        utc_offset: utc_offset(schema, timezone, tz_policy)?,
+                    },
+                }
+            }
+        };
+        Ok(column_type)
+    }
+
+    /// Returns the value at `index` of an array of this type, excluding the array offset.
+    ///
+    /// # Safety
+    /// The array must be of this type and hold more than `index` values.
+    unsafe fn value(&self, array: &ArrowArray, index: usize) -> ValueType {
+        let index = array.offset as usize + index;
+        if matches!(self, ColumnType::Null) || !array.is_valid(index) {
+            return ValueType::Missing(MissingValue);
+        }
+        let values = array.buffer(1);
+        match self {
+            ColumnType::Null => ValueType::Missing(MissingValue),
+            ColumnType::Bool => ValueType::Bool(bit(values, index)),
+            ColumnType::Int(int_type) => {
+                let value = int_type.read(values, index);
+                ValueType::Int(match i64::try_from(value) {
+                    Ok(value) => IntValue::Small(value),
+                    Err(_) => IntValue::Big(value.to_string()),
+                })
+            }
+            ColumnType::Float32 => ValueType::Float(read::<f32>(values, index).into()),
+            ColumnType::Float64 => ValueType::Float(read::<f64>(values, index)),
+            ColumnType::String { large } => {
+                let (start, end) = if *large {
+                    (read::<i64>(values, index), read::<i64>(values, index + 1))
+                } else {
+                    (
+                        read::<i32>(values, index).into(),
+                        read::<i32>(values, index + 1).into(),
+                    )
+                };
+                let data = array.buffer(2).cast::<u8>().add(start as usize);
+                ValueType::String(text(data, (end - start) as usize))
+            }
+            ColumnType::StringView => {
+                // Each view is 16 bytes: the length, then the string itself if it fits
+                // in 12 bytes, or its prefix, data buffer index and offset otherwise.
+                let view = values.cast::<u8>().add(index * 16);
+                let length = read::<i32>(view.cast(), 0) as usize;
+                if length <= 12 {
+                    ValueType::String(text(view.add(4), length))
+                } else {
+                    let buffer = read::<i32>(view.cast(), 2) as usize;
+                    let offset = read::<i32>(view.cast(), 3) as usize;
+                    let data = array.buffer(2 + buffer).cast::<u8>().add(offset);
+                    ValueType::String(text(data, length))
+                }
+            }
+            ColumnType::Date32 => {
+                ValueType::Date(DateValue::from_unix_days(read::<i32>(values, index).into()))
+            }
+            ColumnType::Date64 => ValueType::Date(DateValue::from_unix_days(
+                read::<i64>(values, index).div_euclid(MILLISECONDS_PER_DAY),
+            )),
+            ColumnType::Timestamp { unit, utc_offset } => {
+                let seconds = read::<i64>(values, index) as f64 * unit;
+                ValueType::DateTime(match utc_offset {
+                    None => DateTimeValue::from_unix_seconds(seconds),
+                    Some(utc_offset) => DateTimeValue::from_unix_seconds(seconds + utc_offset)
+                        .with_utc_offset(*utc_offset),
+                })
+            }
+            ColumnType::Time { unit, large } => {
+                let value = if *large {
+                    read::<i64>(values, index)
+                } else {
+                    read::<i32>(values, index).into()
+                };
+                ValueType::Time(TimeValue::from_seconds(value as f64 * unit))
+            }
+            ColumnType::Duration { unit } => ValueType::TimeDelta(TimeDeltaValue::from_seconds(
+                read::<i64>(values, index) as f64 * unit,
+            )),
+            ColumnType::Dictionary { indices, values } => {
+                let key = indices.read(array.buffer(1), index) as usize;
+                values.value(&*array.dictionary, key)
+            }
+        }
+    }
+}
+
+/// A dataframe, or any other table, read through its `__arrow_c_stream__` method.
+///
+/// The columns are read in place from the Arrow buffers of each record batch, one
+/// batch at a time, so the dataframe is never converted to Python objects.
+pub struct DataFrameValues {
+    stream: ArrowArrayStream,
+    schema: ArrowSchema,
+}
+
+impl DataFrameValues {
+    /// Exports a table through the Arrow PyCapsule interface. The index of a pandas
+    /// DataFrame is exported as its first columns if `index` is set, and dropped otherwise.
+    /// Either way, `reset_index` returns a copy of the DataFrame, which is exported instead.
+    pub fn new(dataframe: &Bound<'_, PyAny>, index: bool) -> PyResult<DataFrameValues> {
+        let py = dataframe.py();
+        let mut dataframe = dataframe.clone();
+        if dataframe
+            .get_type()
+            .module()?
+            .to_str()?
+            .starts_with("pandas")
+        {
+            let kwargs = [("drop", !index)].into_py_dict(py)?;
+            dataframe = dataframe.call_method(intern!(py, "reset_index"), (), Some(&kwargs))?;
+        }
+        if !dataframe.hasattr(intern!(py, "__arrow_c_stream__"))? {
+            return Err(PyTypeError::new_err(format!(
+                "Cannot write a dataframe of type '{}'. Expected an object with an `__arrow_c_stream__` method, such as a pyarrow Table, a polars DataFrame or a pandas DataFrame (pandas 2.2 or later, with pyarrow installed).",
+                dataframe.get_type().name()?
+            )));
+        }
+        let capsule = dataframe.call_method0(intern!(py, "__arrow_c_stream__"))?;
+        let capsule = capsule.downcast::<PyCapsule>()?;
+        if capsule.name()? != Some(c"arrow_array_stream") {
+            return Err(PyTypeError::new_err(
+                "`__arrow_c_stream__` did not return an 'arrow_array_stream' capsule.",
+            ));
+        }
+
+        let pointer = capsule.pointer().cast::<ArrowArrayStream>();
+        // SAFETY: the capsule holds a stream, which is moved out of it. Its release
+        // callback is cleared so that the capsule does not release it a second time.
+        let mut stream = unsafe {
+            let stream = ptr::read(pointer);
+            (*pointer).release = None;
+            stream
+        };
+        if stream.release.is_none() {
+            return Err(PyTypeError::new_err(
+                "The Arrow stream of the dataframe was already consumed.",
+            ));
+        }
+
+        // SAFETY: an all-zero schema is an empty, released schema.
+        let mut schema: ArrowSchema = unsafe { mem::zeroed() };
+        let get_schema = stream.get_schema.ok_or_else(|| {
+            PyTypeError::new_err("The Arrow stream of the dataframe has no get_schema callback.")
+        })?;
+        // SAFETY: the stream is valid and the schema is empty.
+        let code = unsafe { get_schema(&mut stream, &mut schema) };
+        check(&mut stream, code)?;
+        // SAFETY: the schema was exported by the producer.
+        let format = unsafe { CStr::from_ptr(schema.format) };
+        if format != c"+s" {
+            return Err(PyTypeError::new_err(format!(
+                "Expected an Arrow stream of record batches, got the type '{}'.",
+                format.to_string_lossy()
+            )));
+        }
+        Ok(DataFrameValues { stream, schema })
+    }
+
+    /// The names of the columns, for the header row.
+    pub fn column_names(&self) -> Vec<String> {
+        self.columns()
+            .iter()
+            .map(|column| {
+                // SAFETY: the schema was exported by the producer.
+                unsafe { CStr::from_ptr(column.name) }
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect()
+    }
+
+    /// Reads the record batches of the stream, calling `write_row` with every row of values.
+    pub fn for_each_row(
+        mut self,
+        tz_policy: TimezonePolicy,
+        mut write_row: impl FnMut(Vec<ValueType>) -> PyResult<()>,
+    ) -> PyResult<()> {
+        let column_types = self
+            .columns()
+            .iter()
+            // SAFETY: the schema was exported by the producer.
+            .map(|column| unsafe { ColumnType::parse(column, tz_policy) })
+            .collect::<PyResult<Vec<_>>>()?;
+        let get_next = self.stream.get_next.ok_or_else(|| {
+            PyTypeError::new_err("The Arrow stream of the dataframe has no get_next callback.")
+        })?;
+        loop {
+            // SAFETY: an all-zero array is an empty, released array.
+            let mut batch: ArrowArray = unsafe { mem::zeroed() };
+            // SAFETY: the stream is valid and the array is empty.
+            let code = unsafe { get_next(&mut self.stream, &mut batch) };
+            check(&mut self.stream, code)?;
+            // The end of the stream is a released array.
+            if batch.release.is_none() {
+                return Ok(());
+            }
+            if batch.n_children != column_types.len() as i64 {
+                return Err(PyTypeError::new_err(
+                    "A record batch of the dataframe does not match its schema.",
+                ));
+            }
+            let offset = batch.offset as usize;
+            for row in 0..batch.length as usize {
+                let values = column_types
+                    .iter()
+                    .enumerate()
+                    // SAFETY: the batch has `length` rows and a child array per column,
+                    // whose types were parsed from the schema.
+                    .map(|(column, column_type)| unsafe {
+                        column_type.value(batch.child(column), offset + row)
+                    })
+                    .collect();
+                write_row(values)?;
+            }
+        }
+    }
+
+    /// The schemas of the columns.
+    fn columns(&self) -> &[&ArrowSchema] {
+        if self.schema.n_children == 0 {
+            return &[];
+        }
+        // SAFETY: the schema has `n_children` children, which live as long as their parent.
+        unsafe {
+            slice::from_raw_parts(
+                self.schema.children.cast::<&ArrowSchema>(),
+                self.schema.n_children as usize,
+            )
+        }
+    }
+}
+
+/// Returns the error of a failed stream callback, if `code` is not zero.
+fn check(stream: &mut ArrowArrayStream, code: c_int) -> PyResult<()> {
+    if code == 0 {
+        return Ok(());
+    }
+    let message = stream
+        .get_last_error
+        // SAFETY: the stream is valid, and the message lives until its next callback.
+        .map(|get_last_error| unsafe { get_last_error(stream) })
+        .filter(|message| !message.is_null())
+        .map(|message| {
+            // SAFETY: the message is a non-null, NUL-terminated string owned by the stream.
+            unsafe { CStr::from_ptr(message) }
+                .to_string_lossy()
+                .into_owned()
+        })
+        .unwrap_or_else(|| format!("error code {code}"));
+    Err(PyValueError::new_err(format!(
+        "Failed to read the dataframe: {message}"
+    )))
+}
+
+/// Returns the UTC offset in seconds of a timestamp column with the given timezone.
+///
+/// Arrow timestamps are stored in UTC, so the local time of a named timezone other than
+/// UTC is unknown and the timezone can only be converted to UTC.
+fn utc_offset(
+    schema: &ArrowSchema,
+    timezone: &str,
+    tz_policy: TimezonePolicy,
+) -> PyResult<Option<f64>> {
+    if timezone.is_empty() {
+        return Ok(None);
+    }
+    if matches!(timezone, "UTC" | "Etc/UTC" | "GMT" | "Z") {
+        return Ok(Some(0.0));
+    }
+    let fixed_offset = timezone.strip_prefix(['+', '-']).and_then(|offset| {
+        let (hours, minutes) = offset
+            .split_once(':')
+            .or_else(|| offset.is_char_boundary(2).then(|| offset.split_at(2)))?;
+        let seconds = hours.parse::<f64>().ok()? * 3600.0 + minutes.parse::<f64>().ok()? * 60.0;
+        Some(if timezone.starts_with('-') {
+            -seconds
+        } else {
+            seconds
+        })
+    });
+    match (fixed_offset, tz_policy) {
+        (Some(offset), _) => Ok(Some(offset)),
+        (None, TimezonePolicy::Drop) => Err(PyTypeError::new_err(format!(
+            "Cannot drop the timezone '{timezone}' of the column '{}', since its local times are unknown. Convert the column to a naive timestamp first, or use tz_policy='utc'.",
+            // SAFETY: the schema was exported by the producer.
+            unsafe { CStr::from_ptr(schema.name) }.to_string_lossy()
+        ))),
+        (None, _) => Ok(Some(0.0)),
+    }
+}
+
+fn unsupported(schema: &ArrowSchema, format: &str) -> PyErr {
+    PyTypeError::new_err(format!(
+        "Unsupported Arrow type '{format}' of the column '{}'. Expected strings, numbers, booleans, nulls, dates, times, timestamps or durations.",
+        // SAFETY: the schema was exported by the producer.
+        unsafe { CStr::from_ptr(schema.name) }.to_string_lossy()
+    ))
+}
+
+/// Reads the value at `index` of a buffer of `T`.
+///
+/// # Safety
+/// The buffer must hold more than `index` values of `T`.
+unsafe fn read<T: Copy>(buffer: *const c_void, index: usize) -> T {
+    buffer.cast::<T>().add(index).read_unaligned()
+}
+
+/// Reads the bit at `index` of a bitmap, least significant bit first.
+///
+/// # Safety
+/// The bitmap must hold more than `index` bits.
+unsafe fn bit(bitmap: *const c_void, index: usize) -> bool {
+    read::<u8>(bitmap, index / 8) & (1 << (index % 8)) != 0
+}
+
+/// Copies `length` bytes of UTF-8 text.
+///
+/// # Safety
+/// The data must hold `length` bytes.
+unsafe fn text(data: *const u8, length: usize) -> String {
+    String::from_utf8_lossy(slice::from_raw_parts(data, length)).into_owned()
+}
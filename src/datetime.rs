@@ -0,0 +1,320 @@
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{
+    PyDate, PyDateAccess, PyDateTime, PyDelta, PyDeltaAccess, PyTime, PyTimeAccess, PyTzInfo,
+    PyTzInfoAccess,
+};
+use rust_xlsxwriter::{ExcelDateTime, XlsxError};
+
+use crate::format::{ExcelFormat, FormatId, FormatRegistry};
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// The policy applied to timezone-aware `datetime` and `time` values,
+/// since Excel dates and times have no timezone.
+///
+/// Accepted values from Python are `"utc"`, `"drop"` and `"raise"`.
+#[derive(Clone, Copy, PartialEq)]
+pub enum TimezonePolicy {
+    /// Convert the value to UTC, then drop the timezone.
+    Utc,
+    /// Drop the timezone, keeping the local date and time.
+    Drop,
+    /// Raise a `ParameterError`.
+    Raise,
+}
+
+impl<'py> FromPyObject<'py> for TimezonePolicy {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        match ob.extract::<&str>()? {
+            "utc" => Ok(TimezonePolicy::Utc),
+            "drop" => Ok(TimezonePolicy::Drop),
+            "raise" => Ok(TimezonePolicy::Raise),
+            other => Err(PyValueError::new_err(format!(
+                "Unknown tz_policy '{other}'. Expected one of: 'utc', 'drop', 'raise'."
+            ))),
+        }
+    }
+}
+
+impl TimezonePolicy {
+    /// Applies the policy to an Excel serial date with the given UTC offset in seconds.
+    fn apply(&self, serial: f64, utc_offset: Option<f64>) -> Result<f64, XlsxError> {
+        match (utc_offset, self) {
+            (None, _) | (Some(_), TimezonePolicy::Drop) => Ok(serial),
+            (Some(utc_offset), TimezonePolicy::Utc) => Ok(serial - utc_offset / SECONDS_PER_DAY),
+            (Some(_), TimezonePolicy::Raise) => Err(XlsxError::ParameterError(
+                "Timezone-aware datetimes and times are not supported with tz_policy='raise'. Use tz_policy='utc' or tz_policy='drop'.".to_string(),
+            )),
+        }
+    }
+}
+
+/// The kind of a date or time value, each with its own default number format.
+#[derive(Clone, Copy)]
+pub enum DateTimeKind {
+    Date,
+    DateTime,
+    Time,
+    TimeDelta,
+}
+
+/// The default number formats of date and time values in a workbook.
+/// Their formats are only created once a value of the kind is written.
+pub struct DateTimeFormats {
+    num_formats: [String; 4],
+    ids: [Option<FormatId>; 4],
+}
+
+impl DateTimeFormats {
+    pub fn new(date: String, datetime: String, time: String, timedelta: String) -> DateTimeFormats {
+        DateTimeFormats {
+            num_formats: [date, datetime, time, timedelta],
+            ids: [None; 4],
+        }
+    }
+
+    /// Returns the id of the default format for the given kind of value.
+    pub fn id(&mut self, formats: &mut FormatRegistry, kind: DateTimeKind) -> FormatId {
+        let index = kind as usize;
+        *self.ids[index].get_or_insert_with(|| {
+            formats.id(&ExcelFormat::with_num_format(
+                self.num_formats[index].clone(),
+            ))
+        })
+    }
+}
+
+/// A `datetime.date` passed from Python.
+#[derive(Clone)]
+pub struct DateValue {
+    year: u16,
+    month: u8,
+    day: u8,
+}
+
+impl<'py> FromPyObject<'py> for DateValue {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        let date = ob.downcast::<PyDate>()?;
+        Ok(DateValue {
+            // Python years are always between 1 and 9999.
+            year: date.get_year() as u16,
+            month: date.get_month(),
+            day: date.get_day(),
+        })
+    }
+}
+
+impl DateValue {
+    /// Returns the date `days` after the Unix epoch, using the civil calendar algorithm
+    /// of Howard Hinnant. Years outside of the `u16` range are clamped, and then rejected
+    /// by Excel, which only supports the years 1900 to 9999.
+    pub fn from_unix_days(days: i64) -> DateValue {
+        let days = days.saturating_add(719_468);
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+        let month = if month_index < 10 {
+            month_index + 3
+        } else {
+            month_index - 9
+        };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        DateValue {
+            year: year.clamp(0, i64::from(u16::MAX)) as u16,
+            month: month as u8,
+            day: day as u8,
+        }
+    }
+
+    pub fn to_excel(&self) -> Result<ExcelDateTime, XlsxError> {
+        ExcelDateTime::from_ymd(self.year, self.month, self.day)
+    }
+}
+
+/// A `datetime.time` passed from Python, with its UTC offset in seconds if it is timezone-aware.
+#[derive(Clone)]
+pub struct TimeValue {
+    hour: u8,
+    minute: u8,
+    second: f64,
+    utc_offset: Option<f64>,
+}
+
+impl<'py> FromPyObject<'py> for TimeValue {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        let time = ob.downcast::<PyTime>()?;
+        Ok(TimeValue {
+            hour: time.get_hour(),
+            minute: time.get_minute(),
+            second: time.get_second() as f64 + time.get_microsecond() as f64 / 1e6,
+            utc_offset: utc_offset(ob, time.get_tzinfo())?,
+        })
+    }
+}
+
+impl TimeValue {
+    /// Creates a naive time from a number of seconds since midnight.
+    pub fn from_seconds(seconds: f64) -> TimeValue {
+        TimeValue {
+            hour: (seconds / 3600.0) as u8,
+            minute: (seconds % 3600.0 / 60.0) as u8,
+            second: seconds % 60.0,
+            utc_offset: None,
+        }
+    }
+
+    pub fn to_excel(&self, tz_policy: TimezonePolicy) -> Result<ExcelDateTime, XlsxError> {
+        let time = ExcelDateTime::from_hms(self.hour.into(), self.minute, self.second)?;
+        let serial = tz_policy.apply(time.to_excel(), self.utc_offset)?;
+        // Converting to UTC can move the time to the previous or next day.
+        ExcelDateTime::from_serial_datetime(serial.rem_euclid(1.0))
+    }
+}
+
+/// A `datetime.datetime` passed from Python.
+#[derive(Clone)]
+pub struct DateTimeValue {
+    date: DateValue,
+    time: TimeValue,
+}
+
+impl<'py> FromPyObject<'py> for DateTimeValue {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        let datetime = ob.downcast::<PyDateTime>()?;
+        Ok(DateTimeValue {
+            date: DateValue {
+                year: datetime.get_year() as u16,
+                month: datetime.get_month(),
+                day: datetime.get_day(),
+            },
+            time: TimeValue {
+                hour: datetime.get_hour(),
+                minute: datetime.get_minute(),
+                second: datetime.get_second() as f64 + datetime.get_microsecond() as f64 / 1e6,
+                utc_offset: utc_offset(ob, datetime.get_tzinfo())?,
+            },
+        })
+    }
+}
+
+impl DateTimeValue {
+    /// Returns the naive datetime `seconds` after the Unix epoch, such as a `numpy.datetime64`.
+    pub fn from_unix_seconds(seconds: f64) -> DateTimeValue {
+        let days = (seconds / SECONDS_PER_DAY).floor();
+        let second = seconds - days * SECONDS_PER_DAY;
+        DateTimeValue {
+            date: DateValue::from_unix_days(days as i64),
+            time: TimeValue::from_seconds(second),
+        }
+    }
+
+    /// Marks a datetime built from local seconds as timezone-aware, `utc_offset` seconds ahead of UTC.
+    pub fn with_utc_offset(mut self, utc_offset: f64) -> DateTimeValue {
+        self.time.utc_offset = Some(utc_offset);
+        self
+    }
+
+    pub fn to_excel(&self, tz_policy: TimezonePolicy) -> Result<ExcelDateTime, XlsxError> {
+        let datetime = self.date.to_excel()?.and_hms(
+            self.time.hour.into(),
+            self.time.minute,
+            self.time.second,
+        )?;
+        match self.time.utc_offset {
+            None => Ok(datetime),
+            Some(_) => ExcelDateTime::from_serial_datetime(
+                tz_policy.apply(datetime.to_excel(), self.time.utc_offset)?,
+            ),
+        }
+    }
+}
+
+/// A `datetime.timedelta` passed from Python, as a number of days.
+#[derive(Clone)]
+pub struct TimeDeltaValue {
+    days: f64,
+}
+
+impl<'py> FromPyObject<'py> for TimeDeltaValue {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        let delta = ob.downcast::<PyDelta>()?;
+        Ok(TimeDeltaValue {
+            days: total_seconds(delta) / SECONDS_PER_DAY,
+        })
+    }
+}
+
+impl TimeDeltaValue {
+    pub fn from_seconds(seconds: f64) -> TimeDeltaValue {
+        TimeDeltaValue {
+            days: seconds / SECONDS_PER_DAY,
+        }
+    }
+
+    /// Excel durations are serial dates counted from zero, so negative durations are rejected.
+    pub fn to_excel(&self) -> Result<ExcelDateTime, XlsxError> {
+        ExcelDateTime::from_serial_datetime(self.days)
+    }
+}
+
+fn total_seconds(delta: &Bound<'_, PyDelta>) -> f64 {
+    delta.get_days() as f64 * SECONDS_PER_DAY
+        + delta.get_seconds() as f64
+        + delta.get_microseconds() as f64 / 1e6
+}
+
+/// Returns the UTC offset in seconds of a `datetime` or `time`, or `None` if it is naive.
+fn utc_offset<'py>(
+    ob: &Bound<'py, PyAny>,
+    tzinfo: Option<Bound<'py, PyTzInfo>>,
+) -> PyResult<Option<f64>> {
+    if tzinfo.is_none() {
+        return Ok(None);
+    }
+    let offset = ob.call_method0("utcoffset")?;
+    if offset.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(total_seconds(offset.downcast::<PyDelta>()?)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ymd(date: &DateValue) -> (u16, u8, u8) {
+        (date.year, date.month, date.day)
+    }
+
+    #[test]
+    fn from_unix_days_follows_the_civil_calendar() {
+        assert_eq!(ymd(&DateValue::from_unix_days(0)), (1970, 1, 1));
+        assert_eq!(ymd(&DateValue::from_unix_days(-1)), (1969, 12, 31));
+        assert_eq!(ymd(&DateValue::from_unix_days(11_016)), (2000, 2, 29));
+        assert_eq!(ymd(&DateValue::from_unix_days(19_723)), (2024, 1, 1));
+        assert_eq!(ymd(&DateValue::from_unix_days(-25_567)), (1900, 1, 1));
+    }
+
+    #[test]
+    fn from_unix_days_clamps_years_rejected_by_excel() {
+        assert_eq!(DateValue::from_unix_days(-1_000_000).year, 0);
+        assert_eq!(DateValue::from_unix_days(100_000_000).year, u16::MAX);
+        assert!(DateValue::from_unix_days(-1_000_000).to_excel().is_err());
+        assert!(DateValue::from_unix_days(100_000_000).to_excel().is_err());
+        assert_eq!(DateValue::from_unix_days(i64::MAX).year, u16::MAX);
+        assert_eq!(DateValue::from_unix_days(i64::MIN).year, 0);
+    }
+
+    #[test]
+    fn from_unix_seconds_splits_the_date_and_time() {
+        let datetime = DateTimeValue::from_unix_seconds(-1.0);
+        assert_eq!(ymd(&datetime.date), (1969, 12, 31));
+        assert_eq!((datetime.time.hour, datetime.time.minute), (23, 59));
+        assert_eq!(datetime.time.second, 59.0);
+    }
+}
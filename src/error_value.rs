@@ -0,0 +1,216 @@
+/// This module contains the Excel error values, such as `#N/A`, and the policy
+/// applied to NaN and infinite numbers, which Excel cannot store.
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+/// The error values that an Excel cell can hold.
+const ERROR_VALUES: &[&str] = &[
+    "#N/A", "#NUM!", "#DIV/0!", "#VALUE!", "#REF!", "#NAME?", "#NULL!",
+];
+
+/// The `ExcelErrorValue` is an Excel error value, such as `#N/A`, which can be
+/// written with `ExcelWorkbook.write` like any other value.
+///
+/// The error cell is written as a formula returning the error, so Excel treats
+/// it as a real error, e.g. `ISNA` returns `TRUE` for `ExcelErrorValue.NA`.
+///
+/// ## Examples
+/// The following example demonstrates marking a missing rate as `#N/A`.
+/// ```
+/// from pyaccelsx import ExcelWorkbook, ExcelErrorValue
+///
+/// def main():
+///     workbook = ExcelWorkbook()
+///     workbook.add_worksheet()
+///
+///     workbook.write(0, 0, ExcelErrorValue.NA)
+///     workbook.write(1, 0, ExcelErrorValue("#DIV/0!"))
+///
+///     workbook.save("example.xlsx")
+/// ```
+#[pyclass(frozen, eq, hash)]
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct ExcelErrorValue {
+    value: &'static str,
+}
+
+#[pymethods]
+impl ExcelErrorValue {
+    #[new]
+    /// Create an error value from its text, one of `"#N/A"`, `"#NUM!"`, `"#DIV/0!"`,
+    /// `"#VALUE!"`, `"#REF!"`, `"#NAME?"` or `"#NULL!"`.
+    pub fn new(value: &str) -> PyResult<ExcelErrorValue> {
+        ExcelErrorValue::parse(value).ok_or_else(|| {
+            PyValueError::new_err(format!(
+                "Unknown Excel error value '{value}'. Expected one of: {}.",
+                ERROR_VALUES
+                    .iter()
+                    .map(|value| format!("'{value}'"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+        })
+    }
+
+    #[classattr]
+    #[pyo3(name = "NA")]
+    fn na() -> ExcelErrorValue {
+        ExcelErrorValue { value: "#N/A" }
+    }
+
+    #[classattr]
+    #[pyo3(name = "NUM")]
+    fn num() -> ExcelErrorValue {
+        ExcelErrorValue { value: "#NUM!" }
+    }
+
+    #[classattr]
+    #[pyo3(name = "DIV0")]
+    fn div0() -> ExcelErrorValue {
+        ExcelErrorValue { value: "#DIV/0!" }
+    }
+
+    #[classattr]
+    #[pyo3(name = "VALUE")]
+    fn value_error() -> ExcelErrorValue {
+        ExcelErrorValue { value: "#VALUE!" }
+    }
+
+    #[classattr]
+    #[pyo3(name = "REF")]
+    fn reference() -> ExcelErrorValue {
+        ExcelErrorValue { value: "#REF!" }
+    }
+
+    #[classattr]
+    #[pyo3(name = "NAME")]
+    fn name() -> ExcelErrorValue {
+        ExcelErrorValue { value: "#NAME?" }
+    }
+
+    #[classattr]
+    #[pyo3(name = "NULL")]
+    fn null() -> ExcelErrorValue {
+        ExcelErrorValue { value: "#NULL!" }
+    }
+
+    /// The text of the error value, e.g. `"#N/A"`.
+    #[getter]
+    pub fn value(&self) -> &'static str {
+        self.value
+    }
+
+    fn __repr__(&self) -> String {
+        format!("ExcelErrorValue('{}')", self.value)
+    }
+
+    fn __str__(&self) -> &'static str {
+        self.value
+    }
+}
+
+impl ExcelErrorValue {
+    /// Returns the error value with the given text, if Excel has one.
+    fn parse(value: &str) -> Option<ExcelErrorValue> {
+        ERROR_VALUES
+            .iter()
+            .find(|error| **error == value)
+            .map(|error| ExcelErrorValue { value: error })
+    }
+}
+
+/// The policy applied to NaN or infinite numbers, which Excel cannot store.
+///
+/// Accepted values from Python are `"raise"`, `"missing"`, `"blank"`, an Excel error value such as
+/// `"#N/A"` or `ExcelErrorValue.NA`, or `("text", string)` to write the string instead.
+#[derive(Clone)]
+pub enum NonFinitePolicy {
+    /// Raise a `ParameterError`.
+    Raise,
+    /// Write the number like `None`, which leaves the cell empty unless an `override_value` is given.
+    Missing,
+    /// Leave the cell blank, keeping its format.
+    Blank,
+    /// Write the Excel error value.
+    Error(ExcelErrorValue),
+    /// Write the string instead of the number.
+    Text(String),
+}
+
+impl<'py> FromPyObject<'py> for NonFinitePolicy {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        if let Ok(error) = ob.downcast::<ExcelErrorValue>() {
+            return Ok(NonFinitePolicy::Error(error.get().clone()));
+        }
+        if let Ok((kind, text)) = ob.extract::<(String, String)>() {
+            if kind == "text" {
+                return Ok(NonFinitePolicy::Text(text));
+            }
+            return Err(unknown_policy(&format!("('{kind}', ...)")));
+        }
+        match ob.extract::<&str>()? {
+            "raise" => Ok(NonFinitePolicy::Raise),
+            "missing" => Ok(NonFinitePolicy::Missing),
+            "blank" => Ok(NonFinitePolicy::Blank),
+            other => ExcelErrorValue::parse(other)
+                .map(NonFinitePolicy::Error)
+                .ok_or_else(|| unknown_policy(&format!("'{other}'"))),
+        }
+    }
+}
+
+fn unknown_policy(policy: &str) -> PyErr {
+    PyValueError::new_err(format!(
+        "Unknown policy {policy}. Expected one of: 'raise', 'missing', 'blank', an Excel error value such as '#N/A', or ('text', string)."
+    ))
+}
+
+/// The policies of a workbook for NaN and infinite numbers.
+#[derive(Clone)]
+pub struct NonFinitePolicies {
+    pub nan: NonFinitePolicy,
+    pub inf: NonFinitePolicy,
+}
+
+impl NonFinitePolicies {
+    /// Returns the policy applied to `value`, or `None` if Excel can store it.
+    pub fn get(&self, value: f64) -> Option<&NonFinitePolicy> {
+        if value.is_nan() {
+            Some(&self.nan)
+        } else if value.is_infinite() {
+            Some(&self.inf)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(expression: &std::ffi::CStr) -> PyResult<NonFinitePolicy> {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| py.eval(expression, None, None)?.extract())
+    }
+
+    #[test]
+    fn policies_are_parsed_from_their_python_values() {
+        assert!(matches!(policy(c"'raise'"), Ok(NonFinitePolicy::Raise)));
+        assert!(matches!(policy(c"'missing'"), Ok(NonFinitePolicy::Missing)));
+        assert!(matches!(policy(c"'blank'"), Ok(NonFinitePolicy::Blank)));
+        assert!(
+            matches!(policy(c"'#N/A'"), Ok(NonFinitePolicy::Error(error)) if error.value() == "#N/A")
+        );
+        assert!(
+            matches!(policy(c"('text', 'n/a')"), Ok(NonFinitePolicy::Text(text)) if text == "n/a")
+        );
+    }
+
+    #[test]
+    fn unknown_policies_are_rejected() {
+        let error = policy(c"'blnak'").err().unwrap();
+        Python::with_gil(|py| assert!(error.is_instance_of::<PyValueError>(py)));
+        assert!(policy(c"('txt', 'n/a')").is_err());
+    }
+}
@@ -0,0 +1,168 @@
+/// This module contains the Python exceptions raised by pyaccelsx.
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use pyo3::PyTypeInfo;
+use rust_xlsxwriter::{row_col_to_cell, ColNum, RowNum, XlsxError};
+
+create_exception!(
+    pyaccelsx.errors,
+    ExcelError,
+    PyException,
+    "Base class for all errors raised while building or saving a workbook."
+);
+create_exception!(
+    pyaccelsx.errors,
+    SheetNameError,
+    ExcelError,
+    "Raised when a worksheet name is blank, too long, reused or contains invalid characters."
+);
+create_exception!(
+    pyaccelsx.errors,
+    WorksheetNotFoundError,
+    ExcelError,
+    "Raised when the active worksheet index does not point to an existing worksheet."
+);
+create_exception!(
+    pyaccelsx.errors,
+    RowColumnLimitError,
+    ExcelError,
+    "Raised when a row or column is outside of the Excel worksheet limits."
+);
+create_exception!(
+    pyaccelsx.errors,
+    MergeRangeError,
+    ExcelError,
+    "Raised when a merged range overlaps another merged range or covers a single cell."
+);
+create_exception!(
+    pyaccelsx.errors,
+    StringLengthError,
+    ExcelError,
+    "Raised when a string exceeds the Excel cell limit of 32,767 characters."
+);
+create_exception!(
+    pyaccelsx.errors,
+    HyperlinkError,
+    ExcelError,
+    "Raised when a URL is unsupported or too long, or a worksheet has too many hyperlinks."
+);
+create_exception!(
+    pyaccelsx.errors,
+    RowOrderError,
+    ExcelError,
+    "Raised when a constant memory worksheet is written above its current row."
+);
+create_exception!(
+    pyaccelsx.errors,
+    ExcelIOError,
+    ExcelError,
+    "Raised when the workbook file cannot be written."
+);
+create_exception!(
+    pyaccelsx.errors,
+    ParameterError,
+    ExcelError,
+    "Raised when a value or option is not accepted by Excel."
+);
+
+/// Converts a `rust_xlsxwriter::XlsxError` into the matching Python exception.
+///
+/// The raised exception carries the following attributes:
+/// - `sheet`: The name of the worksheet being written, or `None`
+/// - `cell`: The cell being written in A1 notation (e.g. `"B3"`), or `None`
+/// - `cause`: The original error message from `rust_xlsxwriter`
+///
+/// ## Parameters
+/// - `error`: The error returned by `rust_xlsxwriter`
+/// - `sheet`: The name of the worksheet the error happened in _(optional)_
+/// - `cell`: The `(row, column)` of the cell the error happened in _(optional)_
+///
+/// ## Returns
+/// - A `PyErr` of a subclass of `ExcelError`
+pub fn to_py_err(error: XlsxError, sheet: Option<&str>, cell: Option<(RowNum, ColNum)>) -> PyErr {
+    let cause = error.to_string();
+    match error {
+        XlsxError::SheetnameCannotBeBlank(_)
+        | XlsxError::SheetnameLengthExceeded(_)
+        | XlsxError::SheetnameReused(_)
+        | XlsxError::SheetnameContainsInvalidCharacter(_)
+        | XlsxError::SheetnameStartsOrEndsWithApostrophe(_) => {
+            new_err::<SheetNameError>(cause, sheet, cell)
+        }
+        XlsxError::UnknownWorksheetNameOrIndex(_) => {
+            new_err::<WorksheetNotFoundError>(cause, sheet, cell)
+        }
+        XlsxError::RowColumnLimitError => new_err::<RowColumnLimitError>(cause, sheet, cell),
+        XlsxError::MergeRangeSingleCell | XlsxError::MergeRangeOverlaps(_, _) => {
+            new_err::<MergeRangeError>(cause, sheet, cell)
+        }
+        XlsxError::MaxStringLengthExceeded => new_err::<StringLengthError>(cause, sheet, cell),
+        XlsxError::MaxUrlLengthExceeded | XlsxError::UnknownUrlType(_) => {
+            new_err::<HyperlinkError>(cause, sheet, cell)
+        }
+        XlsxError::IoError(_) | XlsxError::ZipError(_) => {
+            new_err::<ExcelIOError>(cause, sheet, cell)
+        }
+        _ => new_err::<ParameterError>(cause, sheet, cell),
+    }
+}
+
+/// Creates an `ExcelError` subclass exception carrying the `sheet`, `cell`
+/// and `cause` attributes, for errors detected by pyaccelsx itself.
+///
+/// ## Parameters
+/// - `cause`: The description of the error
+/// - `sheet`: The name of the worksheet the error happened in _(optional)_
+/// - `cell`: The `(row, column)` of the cell the error happened in _(optional)_
+pub fn new_err<T: PyTypeInfo>(
+    cause: String,
+    sheet: Option<&str>,
+    cell: Option<(RowNum, ColNum)>,
+) -> PyErr {
+    let cell = cell.map(|(row, column)| row_col_to_cell(row, column));
+
+    let mut message = cause.clone();
+    match (sheet, &cell) {
+        (Some(sheet), Some(cell)) => message.push_str(&format!(" (sheet '{sheet}', cell {cell})")),
+        (Some(sheet), None) => message.push_str(&format!(" (sheet '{sheet}')")),
+        (None, Some(cell)) => message.push_str(&format!(" (cell {cell})")),
+        (None, None) => {}
+    }
+
+    Python::with_gil(|py| {
+        let py_err = PyErr::from_type(py.get_type::<T>(), message);
+        let value = py_err.value(py);
+        // Exception instances always accept new attributes, so these cannot fail.
+        let _ = value.setattr("sheet", sheet);
+        let _ = value.setattr("cell", cell);
+        let _ = value.setattr("cause", cause);
+        py_err
+    })
+}
+
+/// Registers the `pyaccelsx.errors` submodule and its exceptions.
+pub fn register(parent: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = parent.py();
+    let module = PyModule::new(py, "errors")?;
+    module.add("ExcelError", py.get_type::<ExcelError>())?;
+    module.add("SheetNameError", py.get_type::<SheetNameError>())?;
+    module.add(
+        "WorksheetNotFoundError",
+        py.get_type::<WorksheetNotFoundError>(),
+    )?;
+    module.add("RowColumnLimitError", py.get_type::<RowColumnLimitError>())?;
+    module.add("MergeRangeError", py.get_type::<MergeRangeError>())?;
+    module.add("StringLengthError", py.get_type::<StringLengthError>())?;
+    module.add("HyperlinkError", py.get_type::<HyperlinkError>())?;
+    module.add("RowOrderError", py.get_type::<RowOrderError>())?;
+    module.add("ExcelIOError", py.get_type::<ExcelIOError>())?;
+    module.add("ParameterError", py.get_type::<ParameterError>())?;
+    parent.add_submodule(&module)?;
+
+    // Allow `from pyaccelsx.errors import ExcelError`.
+    py.import("sys")?
+        .getattr("modules")?
+        .set_item("pyaccelsx.errors", module)?;
+    Ok(())
+}
@@ -1,6 +1,199 @@
 /// This module contains the formatting for the Excel workbook.
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::ffi::CString;
+use std::hash::{Hash, Hasher};
+
+use pyo3::exceptions::{PyUserWarning, PyValueError};
 use pyo3::prelude::*;
-use rust_xlsxwriter::{Format, FormatAlign, FormatBorder, FormatUnderline};
+use pyo3::types::{PyBool, PyDict, PyFloat, PyString};
+
+use crate::errors;
+use rust_xlsxwriter::{
+    Format, FormatAlign, FormatBorder, FormatDiagonalBorder, FormatPattern, FormatScript,
+    FormatUnderline,
+};
+
+const ALIGN_OPTIONS: &[(&str, FormatAlign)] = &[
+    ("left", FormatAlign::Left),
+    ("center", FormatAlign::Center),
+    ("right", FormatAlign::Right),
+    ("fill", FormatAlign::Fill),
+    ("justify", FormatAlign::Justify),
+    ("center_across", FormatAlign::CenterAcross),
+    ("distributed", FormatAlign::Distributed),
+    ("top", FormatAlign::Top),
+    ("bottom", FormatAlign::Bottom),
+    ("vertical_center", FormatAlign::VerticalCenter),
+    ("vertical_distributed", FormatAlign::VerticalDistributed),
+    ("vertical_justify", FormatAlign::VerticalJustify),
+];
+
+const VALIGN_OPTIONS: &[(&str, FormatAlign)] = &[
+    ("top", FormatAlign::Top),
+    ("bottom", FormatAlign::Bottom),
+    ("center", FormatAlign::VerticalCenter),
+    ("justify", FormatAlign::VerticalJustify),
+    ("distributed", FormatAlign::VerticalDistributed),
+    ("vertical_center", FormatAlign::VerticalCenter),
+    ("vertical_justify", FormatAlign::VerticalJustify),
+    ("vertical_distributed", FormatAlign::VerticalDistributed),
+];
+
+const BORDER_OPTIONS: &[(&str, FormatBorder)] = &[
+    ("none", FormatBorder::None),
+    ("thin", FormatBorder::Thin),
+    ("medium", FormatBorder::Medium),
+    ("dashed", FormatBorder::Dashed),
+    ("dotted", FormatBorder::Dotted),
+    ("thick", FormatBorder::Thick),
+    ("double", FormatBorder::Double),
+    ("hair", FormatBorder::Hair),
+    ("medium_dashed", FormatBorder::MediumDashed),
+    ("dash_dot", FormatBorder::DashDot),
+    ("medium_dash_dot", FormatBorder::MediumDashDot),
+    ("dash_dot_dot", FormatBorder::DashDotDot),
+    ("medium_dash_dot_dot", FormatBorder::MediumDashDotDot),
+    ("slant_dash_dot", FormatBorder::SlantDashDot),
+];
+
+const DIAGONAL_TYPE_OPTIONS: &[(&str, FormatDiagonalBorder)] = &[
+    ("up", FormatDiagonalBorder::BorderUp),
+    ("down", FormatDiagonalBorder::BorderDown),
+    ("up_down", FormatDiagonalBorder::BorderUpDown),
+];
+
+const PATTERN_OPTIONS: &[(&str, FormatPattern)] = &[
+    ("none", FormatPattern::None),
+    ("solid", FormatPattern::Solid),
+    ("medium_gray", FormatPattern::MediumGray),
+    ("dark_gray", FormatPattern::DarkGray),
+    ("light_gray", FormatPattern::LightGray),
+    ("dark_horizontal", FormatPattern::DarkHorizontal),
+    ("dark_vertical", FormatPattern::DarkVertical),
+    ("dark_down", FormatPattern::DarkDown),
+    ("dark_up", FormatPattern::DarkUp),
+    ("dark_grid", FormatPattern::DarkGrid),
+    ("dark_trellis", FormatPattern::DarkTrellis),
+    ("light_horizontal", FormatPattern::LightHorizontal),
+    ("light_vertical", FormatPattern::LightVertical),
+    ("light_down", FormatPattern::LightDown),
+    ("light_up", FormatPattern::LightUp),
+    ("light_grid", FormatPattern::LightGrid),
+    ("light_trellis", FormatPattern::LightTrellis),
+    ("gray_125", FormatPattern::Gray125),
+    ("gray_0625", FormatPattern::Gray0625),
+];
+
+const UNDERLINE_OPTIONS: &[(&str, FormatUnderline)] = &[
+    ("single", FormatUnderline::Single),
+    ("double", FormatUnderline::Double),
+    ("single_accounting", FormatUnderline::SingleAccounting),
+    ("double_accounting", FormatUnderline::DoubleAccounting),
+];
+
+const READING_ORDER_OPTIONS: &[(&str, u8)] =
+    &[("context", 0), ("left_to_right", 1), ("right_to_left", 2)];
+
+const FONT_SCHEME_OPTIONS: &[(&str, &str)] =
+    &[("minor", "minor"), ("major", "major"), ("none", "none")];
+
+/// Returns the value of the named option from an option table.
+fn lookup<T: Copy>(options: &[(&str, T)], name: &str) -> Option<T> {
+    options
+        .iter()
+        .find(|(option, _)| *option == name)
+        .map(|(_, value)| *value)
+}
+
+/// Returns the message for an option value that is not in an option table.
+fn invalid_choice<T>(field: &str, value: &str, options: &[(&str, T)]) -> String {
+    let accepted: Vec<String> = options
+        .iter()
+        .map(|(option, _)| format!("'{option}'"))
+        .collect();
+    format!(
+        "Invalid {field} '{value}'. Expected one of: {}.",
+        accepted.join(", ")
+    )
+}
+
+/// Returns whether `color` is a `RRGGBB` hex color, optionally prefixed with `#`.
+fn is_valid_color(color: &str) -> bool {
+    let hex = color.strip_prefix('#').unwrap_or(color);
+    hex.len() == 6 && hex.chars().all(|character| character.is_ascii_hexdigit())
+}
+
+/// A `f64` format option that can be compared and hashed bitwise,
+/// so that `ExcelFormat` can be used as a `FormatRegistry` key.
+#[derive(Clone, Copy, Debug)]
+pub struct HashableFloat(pub f64);
+
+impl PartialEq for HashableFloat {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.to_bits() == other.0.to_bits()
+    }
+}
+
+impl Eq for HashableFloat {}
+
+impl Hash for HashableFloat {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
+}
+
+impl<'py> FromPyObject<'py> for HashableFloat {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        Ok(HashableFloat(ob.extract()?))
+    }
+}
+
+impl<'py> IntoPyObject<'py> for &HashableFloat {
+    type Target = PyFloat;
+    type Output = Bound<'py, PyFloat>;
+    type Error = Infallible;
+
+    fn into_pyobject(self, py: Python<'py>) -> Result<Self::Output, Self::Error> {
+        Ok(PyFloat::new(py, self.0))
+    }
+}
+
+/// A border option, given either as a style name such as `"thin"`, `"medium"`
+/// or `"double"`, or as a boolean where `True` is a thin border.
+#[derive(Clone, PartialEq, Eq, Hash, FromPyObject)]
+pub enum BorderOption {
+    #[pyo3(transparent, annotation = "bool")]
+    Enabled(bool),
+    #[pyo3(transparent, annotation = "str")]
+    Style(String),
+}
+
+impl<'py> IntoPyObject<'py> for &BorderOption {
+    type Target = PyAny;
+    type Output = Bound<'py, PyAny>;
+    type Error = Infallible;
+
+    fn into_pyobject(self, py: Python<'py>) -> Result<Self::Output, Self::Error> {
+        Ok(match self {
+            BorderOption::Enabled(enabled) => PyBool::new(py, *enabled).to_owned().into_any(),
+            BorderOption::Style(style) => PyString::new(py, style).into_any(),
+        })
+    }
+}
+
+impl BorderOption {
+    /// Returns the `rust_xlsxwriter::FormatBorder` for this option.
+    fn to_format_border(&self) -> FormatBorder {
+        match self {
+            BorderOption::Enabled(true) => FormatBorder::Thin,
+            BorderOption::Enabled(false) => FormatBorder::None,
+            BorderOption::Style(style) => {
+                lookup(BORDER_OPTIONS, style).unwrap_or(FormatBorder::Thin)
+            }
+        }
+    }
+}
 
 /// The `ExcelFormat` contains the format options passed from Python
 /// to Rust, and used to create a custom `Format` object depending on
@@ -17,33 +210,84 @@ use rust_xlsxwriter::{Format, FormatAlign, FormatBorder, FormatUnderline};
 ///
 ///     format_option = ExcelFormat(
 ///         align="center",
+///         valign="top",
+///         text_wrap=True,
 ///         bg_color="FFFF00",
+///         fg_color="FF0000",
+///         pattern="light_up",
 ///         bold=True,
+///         font_name="Arial",
+///         font_size=9,
 ///     )
 ///
 ///     workbook.write_string(0, 0, "Hello World!", format_option)
 ///
 ///     workbook.save("example.xlsx")
 /// ```
-#[pyclass(get_all, set_all)]
-#[derive(Clone)]
+///
+/// ## Raises
+/// - `ValueError`: If an option is invalid, such as an unknown `align` or border style, a
+///   malformed hex color or an out-of-range rotation, when the format is created or the option is assigned
+#[pyclass(get_all)]
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
 pub struct ExcelFormat {
     align: Option<String>,
     bg_color: Option<String>,
+    #[pyo3(set)]
     bold: Option<bool>,
-    border: Option<bool>,
-    border_top: Option<bool>,
-    border_bottom: Option<bool>,
-    border_left: Option<bool>,
-    border_right: Option<bool>,
+    border: Option<BorderOption>,
+    border_top: Option<BorderOption>,
+    border_bottom: Option<BorderOption>,
+    border_left: Option<BorderOption>,
+    border_right: Option<BorderOption>,
+    border_color: Option<String>,
+    border_top_color: Option<String>,
+    border_bottom_color: Option<String>,
+    border_left_color: Option<String>,
+    border_right_color: Option<String>,
+    border_diagonal: Option<BorderOption>,
+    border_diagonal_color: Option<String>,
+    border_diagonal_type: Option<String>,
+    fg_color: Option<String>,
     font_color: Option<String>,
+    #[pyo3(set)]
+    font_family: Option<u8>,
+    #[pyo3(set)]
+    font_name: Option<String>,
+    font_scheme: Option<String>,
+    #[pyo3(set)]
+    font_size: Option<HashableFloat>,
+    #[pyo3(set)]
+    hidden: Option<bool>,
+    #[pyo3(set)]
+    indent: Option<u8>,
+    #[pyo3(set)]
+    italic: Option<bool>,
+    #[pyo3(set)]
+    locked: Option<bool>,
+    #[pyo3(set)]
     num_format: Option<String>,
+    pattern: Option<String>,
+    reading_order: Option<String>,
+    rotation: Option<i16>,
+    #[pyo3(set)]
+    shrink_to_fit: Option<bool>,
+    #[pyo3(set)]
+    strikethrough: Option<bool>,
+    #[pyo3(set)]
+    subscript: Option<bool>,
+    #[pyo3(set)]
+    superscript: Option<bool>,
+    #[pyo3(set)]
+    text_wrap: Option<bool>,
     underline: Option<String>,
+    valign: Option<String>,
 }
 
 #[pymethods]
 impl ExcelFormat {
     #[new]
+    #[allow(clippy::too_many_arguments)]
     #[pyo3(signature = (
         align=None,
         bg_color=None,
@@ -56,21 +300,74 @@ impl ExcelFormat {
         font_color=None,
         num_format=None,
         underline=None,
+        *,
+        border_color=None,
+        border_top_color=None,
+        border_bottom_color=None,
+        border_left_color=None,
+        border_right_color=None,
+        border_diagonal=None,
+        border_diagonal_color=None,
+        border_diagonal_type=None,
+        fg_color=None,
+        font_family=None,
+        font_name=None,
+        font_scheme=None,
+        font_size=None,
+        hidden=None,
+        indent=None,
+        italic=None,
+        locked=None,
+        pattern=None,
+        reading_order=None,
+        rotation=None,
+        shrink_to_fit=None,
+        strikethrough=None,
+        subscript=None,
+        superscript=None,
+        text_wrap=None,
+        valign=None,
     ))]
     pub fn new(
         align: Option<String>,
         bg_color: Option<String>,
         bold: Option<bool>,
-        border: Option<bool>,
-        border_top: Option<bool>,
-        border_bottom: Option<bool>,
-        border_left: Option<bool>,
-        border_right: Option<bool>,
+        border: Option<BorderOption>,
+        border_top: Option<BorderOption>,
+        border_bottom: Option<BorderOption>,
+        border_left: Option<BorderOption>,
+        border_right: Option<BorderOption>,
         font_color: Option<String>,
         num_format: Option<String>,
         underline: Option<String>,
-    ) -> ExcelFormat {
-        ExcelFormat {
+        border_color: Option<String>,
+        border_top_color: Option<String>,
+        border_bottom_color: Option<String>,
+        border_left_color: Option<String>,
+        border_right_color: Option<String>,
+        border_diagonal: Option<BorderOption>,
+        border_diagonal_color: Option<String>,
+        border_diagonal_type: Option<String>,
+        fg_color: Option<String>,
+        font_family: Option<u8>,
+        font_name: Option<String>,
+        font_scheme: Option<String>,
+        font_size: Option<HashableFloat>,
+        hidden: Option<bool>,
+        indent: Option<u8>,
+        italic: Option<bool>,
+        locked: Option<bool>,
+        pattern: Option<String>,
+        reading_order: Option<String>,
+        rotation: Option<i16>,
+        shrink_to_fit: Option<bool>,
+        strikethrough: Option<bool>,
+        subscript: Option<bool>,
+        superscript: Option<bool>,
+        text_wrap: Option<bool>,
+        valign: Option<String>,
+    ) -> PyResult<ExcelFormat> {
+        let format = ExcelFormat {
             align,
             bg_color,
             bold,
@@ -79,13 +376,499 @@ impl ExcelFormat {
             border_bottom,
             border_left,
             border_right,
+            border_color,
+            border_top_color,
+            border_bottom_color,
+            border_left_color,
+            border_right_color,
+            border_diagonal,
+            border_diagonal_color,
+            border_diagonal_type,
+            fg_color,
             font_color,
+            font_family,
+            font_name,
+            font_scheme,
+            font_size,
+            hidden,
+            indent,
+            italic,
+            locked,
             num_format,
+            pattern,
+            reading_order,
+            rotation,
+            shrink_to_fit,
+            strikethrough,
+            subscript,
+            superscript,
+            text_wrap,
             underline,
+            valign,
+        };
+        format.validate(true)?;
+        Ok(format)
+    }
+
+    #[setter]
+    fn set_align(&mut self, align: Option<String>) -> PyResult<()> {
+        self.set_checked(|format| &mut format.align, align)
+    }
+
+    #[setter]
+    fn set_bg_color(&mut self, bg_color: Option<String>) -> PyResult<()> {
+        self.set_checked(|format| &mut format.bg_color, bg_color)
+    }
+
+    #[setter]
+    fn set_border(&mut self, border: Option<BorderOption>) -> PyResult<()> {
+        self.set_checked(|format| &mut format.border, border)
+    }
+
+    #[setter]
+    fn set_border_top(&mut self, border_top: Option<BorderOption>) -> PyResult<()> {
+        self.set_checked(|format| &mut format.border_top, border_top)
+    }
+
+    #[setter]
+    fn set_border_bottom(&mut self, border_bottom: Option<BorderOption>) -> PyResult<()> {
+        self.set_checked(|format| &mut format.border_bottom, border_bottom)
+    }
+
+    #[setter]
+    fn set_border_left(&mut self, border_left: Option<BorderOption>) -> PyResult<()> {
+        self.set_checked(|format| &mut format.border_left, border_left)
+    }
+
+    #[setter]
+    fn set_border_right(&mut self, border_right: Option<BorderOption>) -> PyResult<()> {
+        self.set_checked(|format| &mut format.border_right, border_right)
+    }
+
+    #[setter]
+    fn set_border_color(&mut self, border_color: Option<String>) -> PyResult<()> {
+        self.set_checked(|format| &mut format.border_color, border_color)
+    }
+
+    #[setter]
+    fn set_border_top_color(&mut self, border_top_color: Option<String>) -> PyResult<()> {
+        self.set_checked(|format| &mut format.border_top_color, border_top_color)
+    }
+
+    #[setter]
+    fn set_border_bottom_color(&mut self, border_bottom_color: Option<String>) -> PyResult<()> {
+        self.set_checked(
+            |format| &mut format.border_bottom_color,
+            border_bottom_color,
+        )
+    }
+
+    #[setter]
+    fn set_border_left_color(&mut self, border_left_color: Option<String>) -> PyResult<()> {
+        self.set_checked(|format| &mut format.border_left_color, border_left_color)
+    }
+
+    #[setter]
+    fn set_border_right_color(&mut self, border_right_color: Option<String>) -> PyResult<()> {
+        self.set_checked(|format| &mut format.border_right_color, border_right_color)
+    }
+
+    #[setter]
+    fn set_border_diagonal(&mut self, border_diagonal: Option<BorderOption>) -> PyResult<()> {
+        self.set_checked(|format| &mut format.border_diagonal, border_diagonal)
+    }
+
+    #[setter]
+    fn set_border_diagonal_color(&mut self, border_diagonal_color: Option<String>) -> PyResult<()> {
+        self.set_checked(
+            |format| &mut format.border_diagonal_color,
+            border_diagonal_color,
+        )
+    }
+
+    #[setter]
+    fn set_border_diagonal_type(&mut self, border_diagonal_type: Option<String>) -> PyResult<()> {
+        self.set_checked(
+            |format| &mut format.border_diagonal_type,
+            border_diagonal_type,
+        )
+    }
+
+    #[setter]
+    fn set_fg_color(&mut self, fg_color: Option<String>) -> PyResult<()> {
+        self.set_checked(|format| &mut format.fg_color, fg_color)
+    }
+
+    #[setter]
+    fn set_font_color(&mut self, font_color: Option<String>) -> PyResult<()> {
+        self.set_checked(|format| &mut format.font_color, font_color)
+    }
+
+    #[setter]
+    fn set_font_scheme(&mut self, font_scheme: Option<String>) -> PyResult<()> {
+        self.set_checked(|format| &mut format.font_scheme, font_scheme)
+    }
+
+    #[setter]
+    fn set_pattern(&mut self, pattern: Option<String>) -> PyResult<()> {
+        self.set_checked(|format| &mut format.pattern, pattern)
+    }
+
+    #[setter]
+    fn set_reading_order(&mut self, reading_order: Option<String>) -> PyResult<()> {
+        self.set_checked(|format| &mut format.reading_order, reading_order)
+    }
+
+    #[setter]
+    fn set_rotation(&mut self, rotation: Option<i16>) -> PyResult<()> {
+        self.set_checked(|format| &mut format.rotation, rotation)
+    }
+
+    #[setter]
+    fn set_underline(&mut self, underline: Option<String>) -> PyResult<()> {
+        self.set_checked(|format| &mut format.underline, underline)
+    }
+
+    #[setter]
+    fn set_valign(&mut self, valign: Option<String>) -> PyResult<()> {
+        self.set_checked(|format| &mut format.valign, valign)
+    }
+
+    /// Returns a new `ExcelFormat` combining this format with `other`.
+    /// Every option that is set in `other` wins over the same option in this format.
+    ///
+    /// `a.merge(b)` is the same as `a | b`.
+    ///
+    /// ## Parameters
+    /// - `other`: The format to apply on top of this format
+    ///
+    /// ## Examples
+    /// The following example demonstrates adding bold to a column's number format.
+    /// ```
+    /// from pyaccelsx import ExcelWorkbook, ExcelFormat
+    ///
+    /// def main():
+    ///     workbook = ExcelWorkbook()
+    ///     workbook.add_worksheet()
+    ///
+    ///     amount_format = ExcelFormat(num_format="#,##0.00", align="right")
+    ///     total_format = amount_format | ExcelFormat(bold=True, border_top="double")
+    ///
+    ///     workbook.write(0, 0, 120.5, format_option=amount_format)
+    ///     workbook.write(1, 0, 120.5, format_option=total_format)
+    ///
+    ///     workbook.save("example.xlsx")
+    /// ```
+    pub fn merge(&self, other: &ExcelFormat) -> ExcelFormat {
+        self.overlay(other)
+    }
+
+    fn __or__(&self, other: PyRef<'_, ExcelFormat>) -> ExcelFormat {
+        self.overlay(&other)
+    }
+
+    #[pyo3(signature = (**options))]
+    /// Returns a copy of this format with the given options replaced.
+    /// Unlike `merge`, an option can be cleared by passing `None`.
+    ///
+    /// ## Parameters
+    /// - `**options`: `ExcelFormat` options to replace
+    ///
+    /// ## Examples
+    /// The following example demonstrates replacing options of a format.
+    /// ```
+    /// from pyaccelsx import ExcelFormat
+    ///
+    /// def main():
+    ///     header_format = ExcelFormat(bold=True, bg_color="DDDDDD")
+    ///     plain_header_format = header_format.replace(bg_color=None, italic=True)
+    /// ```
+    pub fn replace<'py>(
+        slf: &Bound<'py, Self>,
+        options: Option<&Bound<'py, PyDict>>,
+    ) -> PyResult<Bound<'py, ExcelFormat>> {
+        let replaced = Bound::new(slf.py(), slf.borrow().clone())?;
+        if let Some(options) = options {
+            // Assign through the field setters, so that unknown options raise `AttributeError`.
+            for (name, value) in options {
+                replaced.setattr(name.downcast::<PyString>()?, value)?;
+            }
         }
+        Ok(replaced)
     }
 }
 
+impl ExcelFormat {
+    /// Assigns an option, raising `ValueError` if the value is invalid.
+    fn set_checked<T: Clone>(
+        &mut self,
+        field: fn(&mut ExcelFormat) -> &mut Option<T>,
+        value: Option<T>,
+    ) -> PyResult<()> {
+        let mut option = ExcelFormat::default();
+        *field(&mut option) = value.clone();
+        option.validate(true)?;
+        *field(self) = value;
+        Ok(())
+    }
+
+    /// Returns a new `ExcelFormat` with the options of `self`, overridden by
+    /// every option that is set in `other`.
+    pub fn overlay(&self, other: &ExcelFormat) -> ExcelFormat {
+        // Destructure every field, so that a new option cannot be forgotten here.
+        let ExcelFormat {
+            align,
+            bg_color,
+            bold,
+            border,
+            border_top,
+            border_bottom,
+            border_left,
+            border_right,
+            border_color,
+            border_top_color,
+            border_bottom_color,
+            border_left_color,
+            border_right_color,
+            border_diagonal,
+            border_diagonal_color,
+            border_diagonal_type,
+            fg_color,
+            font_color,
+            font_family,
+            font_name,
+            font_scheme,
+            font_size,
+            hidden,
+            indent,
+            italic,
+            locked,
+            num_format,
+            pattern,
+            reading_order,
+            rotation,
+            shrink_to_fit,
+            strikethrough,
+            subscript,
+            superscript,
+            text_wrap,
+            underline,
+            valign,
+        } = other.clone();
+        let base = self.clone();
+        ExcelFormat {
+            align: align.or(base.align),
+            bg_color: bg_color.or(base.bg_color),
+            bold: bold.or(base.bold),
+            border: border.or(base.border),
+            border_top: border_top.or(base.border_top),
+            border_bottom: border_bottom.or(base.border_bottom),
+            border_left: border_left.or(base.border_left),
+            border_right: border_right.or(base.border_right),
+            border_color: border_color.or(base.border_color),
+            border_top_color: border_top_color.or(base.border_top_color),
+            border_bottom_color: border_bottom_color.or(base.border_bottom_color),
+            border_left_color: border_left_color.or(base.border_left_color),
+            border_right_color: border_right_color.or(base.border_right_color),
+            border_diagonal: border_diagonal.or(base.border_diagonal),
+            border_diagonal_color: border_diagonal_color.or(base.border_diagonal_color),
+            border_diagonal_type: border_diagonal_type.or(base.border_diagonal_type),
+            fg_color: fg_color.or(base.fg_color),
+            font_color: font_color.or(base.font_color),
+            font_family: font_family.or(base.font_family),
+            font_name: font_name.or(base.font_name),
+            font_scheme: font_scheme.or(base.font_scheme),
+            font_size: font_size.or(base.font_size),
+            hidden: hidden.or(base.hidden),
+            indent: indent.or(base.indent),
+            italic: italic.or(base.italic),
+            locked: locked.or(base.locked),
+            num_format: num_format.or(base.num_format),
+            pattern: pattern.or(base.pattern),
+            reading_order: reading_order.or(base.reading_order),
+            rotation: rotation.or(base.rotation),
+            shrink_to_fit: shrink_to_fit.or(base.shrink_to_fit),
+            strikethrough: strikethrough.or(base.strikethrough),
+            subscript: subscript.or(base.subscript),
+            superscript: superscript.or(base.superscript),
+            text_wrap: text_wrap.or(base.text_wrap),
+            underline: underline.or(base.underline),
+            valign: valign.or(base.valign),
+        }
+    }
+
+    /// Returns a format with a border of the given style and color on each of the
+    /// `[top, bottom, left, right]` sides that is `true`.
+    pub fn borders(style: &BorderOption, color: Option<&String>, sides: [bool; 4]) -> ExcelFormat {
+        let [top, bottom, left, right] = sides.map(|side| side.then(|| style.clone()));
+        let [top_color, bottom_color, left_color, right_color] =
+            sides.map(|side| color.filter(|_| side).cloned());
+        ExcelFormat {
+            border_top: top,
+            border_bottom: bottom,
+            border_left: left,
+            border_right: right,
+            border_top_color: top_color,
+            border_bottom_color: bottom_color,
+            border_left_color: left_color,
+            border_right_color: right_color,
+            ..ExcelFormat::default()
+        }
+    }
+
+    /// Returns a format with only the given number format.
+    pub fn with_num_format(num_format: String) -> ExcelFormat {
+        ExcelFormat {
+            num_format: Some(num_format),
+            ..ExcelFormat::default()
+        }
+    }
+
+    /// Returns the format of hyperlinks, the blue underlined font of Excel's "Hyperlink" style.
+    pub fn hyperlink() -> ExcelFormat {
+        ExcelFormat {
+            font_color: Some("0563C1".to_string()),
+            underline: Some("single".to_string()),
+            ..ExcelFormat::default()
+        }
+    }
+
+    /// Validates a border style and color given outside of an `ExcelFormat`,
+    /// such as the options of `ExcelWorkbook.draw_border`.
+    pub fn validate_border(
+        style: &BorderOption,
+        color: Option<&String>,
+        strict: bool,
+    ) -> PyResult<()> {
+        ExcelFormat {
+            border: Some(style.clone()),
+            border_color: color.cloned(),
+            ..ExcelFormat::default()
+        }
+        .validate(strict)
+    }
+
+    /// Raises `ValueError` for invalid options, or only warns if `strict` is not set.
+    pub fn validate(&self, strict: bool) -> PyResult<()> {
+        let errors: Vec<String> = self
+            .invalid_options()
+            .into_iter()
+            .map(|(_, message)| message)
+            .collect();
+        report_invalid_options(errors, strict)
+    }
+
+    /// Returns the name and the error message of every invalid option.
+    fn invalid_options(&self) -> Vec<(&'static str, String)> {
+        let mut errors = Vec::new();
+
+        check_choice(&mut errors, "align", &self.align, ALIGN_OPTIONS);
+        check_choice(&mut errors, "valign", &self.valign, VALIGN_OPTIONS);
+        check_choice(&mut errors, "underline", &self.underline, UNDERLINE_OPTIONS);
+        check_choice(&mut errors, "pattern", &self.pattern, PATTERN_OPTIONS);
+        check_choice(
+            &mut errors,
+            "border_diagonal_type",
+            &self.border_diagonal_type,
+            DIAGONAL_TYPE_OPTIONS,
+        );
+        check_choice(
+            &mut errors,
+            "reading_order",
+            &self.reading_order,
+            READING_ORDER_OPTIONS,
+        );
+        check_choice(
+            &mut errors,
+            "font_scheme",
+            &self.font_scheme,
+            FONT_SCHEME_OPTIONS,
+        );
+
+        let borders = [
+            ("border", &self.border),
+            ("border_top", &self.border_top),
+            ("border_bottom", &self.border_bottom),
+            ("border_left", &self.border_left),
+            ("border_right", &self.border_right),
+            ("border_diagonal", &self.border_diagonal),
+        ];
+        for (field, border) in borders {
+            if let Some(BorderOption::Style(style)) = border {
+                if lookup(BORDER_OPTIONS, style).is_none() {
+                    errors.push((field, invalid_choice(field, style, BORDER_OPTIONS)));
+                }
+            }
+        }
+
+        let colors = [
+            ("bg_color", &self.bg_color),
+            ("fg_color", &self.fg_color),
+            ("font_color", &self.font_color),
+            ("border_color", &self.border_color),
+            ("border_top_color", &self.border_top_color),
+            ("border_bottom_color", &self.border_bottom_color),
+            ("border_left_color", &self.border_left_color),
+            ("border_right_color", &self.border_right_color),
+            ("border_diagonal_color", &self.border_diagonal_color),
+        ];
+        for (field, color) in colors {
+            if let Some(color) = color {
+                if !is_valid_color(color) {
+                    errors.push((
+                        field,
+                        format!("Invalid {field} '{color}'. Expected a hex color such as 'FF0000' or '#FF0000'."),
+                    ));
+                }
+            }
+        }
+
+        if let Some(rotation) = self.rotation {
+            if !(-90..=90).contains(&rotation) && rotation != 270 {
+                errors.push((
+                    "rotation",
+                    format!("Invalid rotation {rotation}. Expected an angle from -90 to 90, or 270 for stacked text."),
+                ));
+            }
+        }
+
+        errors
+    }
+}
+
+/// Records an error if `value` is set but is not one of the named `options`.
+fn check_choice<T: Copy>(
+    errors: &mut Vec<(&'static str, String)>,
+    field: &'static str,
+    value: &Option<String>,
+    options: &[(&str, T)],
+) {
+    if let Some(value) = value {
+        if lookup(options, value).is_none() {
+            errors.push((field, invalid_choice(field, value, options)));
+        }
+    }
+}
+
+/// Raises `ValueError` with the given messages, or emits them as `UserWarning`s
+/// if `strict` is not set.
+fn report_invalid_options(errors: Vec<String>, strict: bool) -> PyResult<()> {
+    if errors.is_empty() {
+        return Ok(());
+    }
+    if strict {
+        return Err(PyValueError::new_err(errors.join("\n")));
+    }
+    Python::with_gil(|py| {
+        let category = py.get_type::<PyUserWarning>();
+        for message in errors {
+            let message = CString::new(message)?;
+            PyErr::warn(py, &category, &message, 1)?;
+        }
+        Ok(())
+    })
+}
+
 /// Creates a `rust_xlsxwriter::Format` object from the `ExcelFormat`
 /// options passed from Python.
 ///
@@ -94,72 +877,376 @@ impl ExcelFormat {
 ///
 /// ## Returns
 /// - A `rust_xlsxwriter::Format` object
-pub fn create_format(format_option: ExcelFormat) -> Format {
+pub fn create_format(format_option: &ExcelFormat) -> Format {
     let mut format = Format::new();
 
-    if let Some(align) = format_option.align {
-        format = format.set_align(match align.as_str() {
-            "left" => FormatAlign::Left,
-            "center" => FormatAlign::Center,
-            "right" => FormatAlign::Right,
-            "fill" => FormatAlign::Fill,
-            "justify" => FormatAlign::Justify,
-            "center_across" => FormatAlign::CenterAcross,
-            "distributed" => FormatAlign::Distributed,
-            "top" => FormatAlign::Top,
-            "bottom" => FormatAlign::Bottom,
-            "vertical_center" => FormatAlign::VerticalCenter,
-            "vertical_distributed" => FormatAlign::VerticalDistributed,
-            "vertical_justify" => FormatAlign::VerticalJustify,
-            _ => FormatAlign::General,
-        })
+    if let Some(align) = &format_option.align {
+        format = format.set_align(lookup(ALIGN_OPTIONS, align).unwrap_or(FormatAlign::General))
+    }
+
+    // Unlike `align`, an unknown `valign` is skipped, since `FormatAlign::General`
+    // would also reset the horizontal alignment.
+    if let Some(valign) = &format_option.valign {
+        if let Some(valign) = lookup(VALIGN_OPTIONS, valign) {
+            format = format.set_align(valign);
+        }
+    }
+
+    if format_option.text_wrap.unwrap_or(false) {
+        format = format.set_text_wrap();
+    }
+
+    if let Some(indent) = format_option.indent {
+        format = format.set_indent(indent);
+    }
+
+    if let Some(rotation) = format_option.rotation {
+        format = format.set_rotation(rotation);
+    }
+
+    if format_option.shrink_to_fit.unwrap_or(false) {
+        format = format.set_shrink();
+    }
+
+    if let Some(reading_order) = &format_option.reading_order {
+        format =
+            format.set_reading_direction(lookup(READING_ORDER_OPTIONS, reading_order).unwrap_or(0));
     }
 
-    if let Some(bg_color) = format_option.bg_color {
+    // Without a pattern, `bg_color` is a solid fill. With a pattern, `fg_color`
+    // is the color of the pattern lines and `bg_color` is the color behind them.
+    if let Some(pattern) = &format_option.pattern {
+        format =
+            format.set_pattern(lookup(PATTERN_OPTIONS, pattern).unwrap_or(FormatPattern::Solid));
+    }
+
+    if let Some(bg_color) = &format_option.bg_color {
         format = format.set_background_color(bg_color.as_str());
     }
 
+    if let Some(fg_color) = &format_option.fg_color {
+        format = format.set_foreground_color(fg_color.as_str());
+    }
+
     if format_option.bold.unwrap_or(false) {
         format = format.set_bold();
     }
 
-    if format_option.border.unwrap_or(false) {
-        format = format.set_border(FormatBorder::Thin);
-    } else {
-        if format_option.border_top.unwrap_or(false) {
-            format = format.set_border_top(FormatBorder::Thin);
+    // The `border` shorthand applies to every side, and each side can override it.
+    if let Some(border) = &format_option.border {
+        format = format.set_border(border.to_format_border());
+    }
+
+    if let Some(border_top) = &format_option.border_top {
+        format = format.set_border_top(border_top.to_format_border());
+    }
+
+    if let Some(border_bottom) = &format_option.border_bottom {
+        format = format.set_border_bottom(border_bottom.to_format_border());
+    }
+
+    if let Some(border_left) = &format_option.border_left {
+        format = format.set_border_left(border_left.to_format_border());
+    }
+
+    if let Some(border_right) = &format_option.border_right {
+        format = format.set_border_right(border_right.to_format_border());
+    }
+
+    if let Some(color) = &format_option.border_color {
+        format = format.set_border_color(color.as_str());
+    }
+
+    if let Some(color) = &format_option.border_top_color {
+        format = format.set_border_top_color(color.as_str());
+    }
+
+    if let Some(color) = &format_option.border_bottom_color {
+        format = format.set_border_bottom_color(color.as_str());
+    }
+
+    if let Some(color) = &format_option.border_left_color {
+        format = format.set_border_left_color(color.as_str());
+    }
+
+    if let Some(color) = &format_option.border_right_color {
+        format = format.set_border_right_color(color.as_str());
+    }
+
+    if let Some(border_diagonal) = &format_option.border_diagonal {
+        format = format
+            .set_border_diagonal(border_diagonal.to_format_border())
+            .set_border_diagonal_type(
+                format_option
+                    .border_diagonal_type
+                    .as_deref()
+                    .and_then(|diagonal_type| lookup(DIAGONAL_TYPE_OPTIONS, diagonal_type))
+                    .unwrap_or(FormatDiagonalBorder::BorderUpDown),
+            );
+    }
+
+    if let Some(color) = &format_option.border_diagonal_color {
+        format = format.set_border_diagonal_color(color.as_str());
+    }
+
+    if let Some(color) = &format_option.font_color {
+        format = format.set_font_color(color.as_str());
+    }
+
+    if let Some(font_family) = format_option.font_family {
+        format = format.set_font_family(font_family);
+    }
+
+    if let Some(font_name) = &format_option.font_name {
+        format = format.set_font_name(font_name.as_str());
+    }
+
+    if let Some(font_scheme) = &format_option.font_scheme {
+        format = format.set_font_scheme(font_scheme.as_str());
+    }
+
+    if let Some(font_size) = format_option.font_size {
+        format = format.set_font_size(font_size.0);
+    }
+
+    if format_option.italic.unwrap_or(false) {
+        format = format.set_italic();
+    }
+
+    if let Some(num_format) = &format_option.num_format {
+        format = format.set_num_format(num_format.as_str());
+    }
+
+    if format_option.strikethrough.unwrap_or(false) {
+        format = format.set_font_strikethrough();
+    }
+
+    // Subscript takes precedence if both scripts are set.
+    if format_option.superscript.unwrap_or(false) {
+        format = format.set_font_script(FormatScript::Superscript);
+    }
+
+    if format_option.subscript.unwrap_or(false) {
+        format = format.set_font_script(FormatScript::Subscript);
+    }
+
+    // Cells are locked by default, which only has an effect on protected worksheets.
+    match format_option.locked {
+        Some(true) => format = format.set_locked(),
+        Some(false) => format = format.set_unlocked(),
+        None => {}
+    }
+
+    if format_option.hidden.unwrap_or(false) {
+        format = format.set_hidden();
+    }
+
+    if let Some(underline) = &format_option.underline {
+        format = format
+            .set_underline(lookup(UNDERLINE_OPTIONS, underline).unwrap_or(FormatUnderline::Single));
+    }
+
+    format
+}
+
+/// A format passed to a write method, either as an `ExcelFormat` or as the
+/// name of a style registered with `ExcelWorkbook.register_style`.
+#[derive(Clone, FromPyObject)]
+#[allow(clippy::large_enum_variant)]
+pub enum FormatOption {
+    #[pyo3(transparent, annotation = "ExcelFormat")]
+    Format(ExcelFormat),
+    #[pyo3(transparent, annotation = "str")]
+    Style(String),
+}
+
+/// The formats passed to a bulk write method, either one format for every cell,
+/// or a sequence with the format of each cell, where `None` leaves a cell unformatted.
+#[derive(FromPyObject)]
+#[allow(clippy::large_enum_variant)]
+pub enum FormatOptions {
+    #[pyo3(transparent, annotation = "ExcelFormat | str")]
+    All(FormatOption),
+    #[pyo3(transparent, annotation = "Sequence[ExcelFormat | str | None]")]
+    Each(Vec<Option<FormatOption>>),
+}
+
+/// The index of a format in a `FormatRegistry`.
+pub type FormatId = usize;
+
+/// The resolved `FormatOptions` of a bulk write.
+pub enum FormatIds {
+    All(Option<FormatId>),
+    Each(Vec<Option<FormatId>>),
+}
+
+impl FormatIds {
+    /// Returns the format of the cell at `index`, or `None` past the end of the sequence.
+    pub fn get(&self, index: usize) -> Option<FormatId> {
+        match self {
+            FormatIds::All(id) => *id,
+            FormatIds::Each(ids) => ids.get(index).copied().flatten(),
+        }
+    }
+}
+
+/// The `FormatRegistry` keeps every `rust_xlsxwriter::Format` built for a workbook,
+/// along with the `ExcelFormat` it was created from. Writing the same `ExcelFormat`
+/// many times then costs a hash lookup instead of building a new `Format`.
+///
+/// Formats are referred to by their `FormatId`, so that they can be combined
+/// and stored per cell cheaply. It also keeps the named styles of the workbook.
+#[derive(Default)]
+pub struct FormatRegistry {
+    strict: bool,
+    ids: HashMap<ExcelFormat, FormatId>,
+    formats: Vec<(ExcelFormat, Format)>,
+    overlays: HashMap<(FormatId, FormatId), FormatId>,
+    styles: HashMap<String, ExcelFormat>,
+}
+
+impl FormatRegistry {
+    pub fn new(strict: bool) -> FormatRegistry {
+        FormatRegistry {
+            strict,
+            ..FormatRegistry::default()
         }
+    }
+
+    /// Whether invalid border options of `draw_border` raise `ValueError`, or only emit a
+    /// `UserWarning`. The options of an `ExcelFormat` are validated when they are set.
+    pub fn strict(&self) -> bool {
+        self.strict
+    }
 
-        if format_option.border_bottom.unwrap_or(false) {
-            format = format.set_border_bottom(FormatBorder::Thin);
+    /// Returns the id of the given format options, creating the `Format` on first use.
+    pub fn id(&mut self, format_option: &ExcelFormat) -> FormatId {
+        get_or_create(&mut self.ids, &mut self.formats, format_option)
+    }
+
+    /// Returns the id of an `ExcelFormat` or a registered style name.
+    pub fn resolve(&mut self, format_option: &FormatOption) -> PyResult<FormatId> {
+        match format_option {
+            FormatOption::Format(format_option) => Ok(self.id(format_option)),
+            FormatOption::Style(name) => {
+                let format_option = self.styles.get(name).ok_or_else(|| unknown_style(name))?;
+                Ok(get_or_create(
+                    &mut self.ids,
+                    &mut self.formats,
+                    format_option,
+                ))
+            }
         }
+    }
 
-        if format_option.border_left.unwrap_or(false) {
-            format = format.set_border_left(FormatBorder::Thin);
+    /// Returns the ids of the formats passed to a bulk write method.
+    pub fn resolve_all(&mut self, format_options: Option<&FormatOptions>) -> PyResult<FormatIds> {
+        match format_options {
+            None => Ok(FormatIds::All(None)),
+            Some(FormatOptions::All(format_option)) => {
+                Ok(FormatIds::All(Some(self.resolve(format_option)?)))
+            }
+            Some(FormatOptions::Each(format_options)) => format_options
+                .iter()
+                .map(|format_option| {
+                    format_option
+                        .as_ref()
+                        .map(|format_option| self.resolve(format_option))
+                        .transpose()
+                })
+                .collect::<PyResult<_>>()
+                .map(FormatIds::Each),
         }
+    }
+
+    /// Returns the `Format` with the given id.
+    pub fn format(&self, id: FormatId) -> &Format {
+        &self.formats[id].1
+    }
+
+    /// Returns the format options of the format with the given id.
+    pub fn options(&self, id: FormatId) -> &ExcelFormat {
+        &self.formats[id].0
+    }
 
-        if format_option.border_right.unwrap_or(false) {
-            format = format.set_border_right(FormatBorder::Thin);
+    /// Returns the id of the format combining `base` with `other`, where every
+    /// option set in `other` wins. See `ExcelFormat.merge`.
+    pub fn overlay(&mut self, base: FormatId, other: FormatId) -> FormatId {
+        if base == other {
+            return base;
         }
+        if let Some(&id) = self.overlays.get(&(base, other)) {
+            return id;
+        }
+        let combined = self.options(base).overlay(self.options(other));
+        let id = self.id(&combined);
+        self.overlays.insert((base, other), id);
+        id
     }
 
-    if let Some(color) = format_option.font_color {
-        format = format.set_font_color(color.as_str());
+    /// Returns the format options of a registered style.
+    pub fn style(&self, name: &str) -> PyResult<&ExcelFormat> {
+        self.styles.get(name).ok_or_else(|| unknown_style(name))
     }
 
-    if let Some(num_format) = format_option.num_format {
-        format = format.set_num_format(num_format.as_str());
+    /// Registers a named style, replacing any style with the same name.
+    pub fn register_style(&mut self, name: String, format_option: ExcelFormat) {
+        self.styles.insert(name, format_option);
     }
 
-    if let Some(underline) = format_option.underline {
-        format = format.set_underline(match underline.as_str() {
-            "single" => FormatUnderline::Single,
-            "double" => FormatUnderline::Double,
-            "single_accounting" => FormatUnderline::SingleAccounting,
-            "double_accounting" => FormatUnderline::DoubleAccounting,
-            _ => FormatUnderline::Single,
-        });
+    /// Returns the number of distinct formats created so far.
+    pub fn len(&self) -> usize {
+        self.formats.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.formats.is_empty()
+    }
+}
+
+/// Returns the id of the given format options, adding them to `formats` on first use.
+fn get_or_create(
+    ids: &mut HashMap<ExcelFormat, FormatId>,
+    formats: &mut Vec<(ExcelFormat, Format)>,
+    format_option: &ExcelFormat,
+) -> FormatId {
+    if let Some(&id) = ids.get(format_option) {
+        return id;
     }
+    let id = formats.len();
+    formats.push((format_option.clone(), create_format(format_option)));
+    ids.insert(format_option.clone(), id);
+    id
+}
+
+fn unknown_style(name: &str) -> PyErr {
+    errors::new_err::<errors::ParameterError>(format!("Unknown style '{name}'."), None, None)
+}
 
-    return format;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pyo3::exceptions::PyValueError;
+    use pyo3::types::PyDict;
+
+    #[test]
+    fn invalid_options_are_rejected_when_created_or_assigned() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let kwargs = PyDict::new(py);
+            kwargs.set_item("align", "centre").unwrap();
+            let err = py
+                .get_type::<ExcelFormat>()
+                .call((), Some(&kwargs))
+                .unwrap_err();
+            assert!(err.is_instance_of::<PyValueError>(py));
+
+            let format = py.get_type::<ExcelFormat>().call0().unwrap();
+            let err = format.setattr("border", "thikc").unwrap_err();
+            assert!(err.is_instance_of::<PyValueError>(py));
+            assert!(format.getattr("border").unwrap().is_none());
+            format.setattr("border", "thin").unwrap();
+            format.setattr("bold", true).unwrap();
+        });
+    }
 }
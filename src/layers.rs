@@ -0,0 +1,756 @@
+use std::collections::{BTreeSet, HashMap};
+use std::ops::RangeInclusive;
+
+use pyo3::prelude::*;
+use rust_xlsxwriter::{ColNum, RowNum, Worksheet, XlsxError};
+
+use crate::errors::{self, RowOrderError};
+use crate::format::{FormatId, FormatRegistry};
+
+/// The number of rows in an Excel worksheet.
+pub const ROW_MAX: RowNum = 1_048_576;
+/// The number of columns in an Excel worksheet.
+pub const COL_MAX: ColNum = 16_384;
+
+/// A format applied to a rectangular range of cells.
+struct RangeLayer {
+    first_row: RowNum,
+    first_column: ColNum,
+    last_row: RowNum,
+    last_column: ColNum,
+    format: FormatId,
+}
+
+impl RangeLayer {
+    /// Creates the layer of the range between two corners, in any order.
+    fn new(
+        (first_row, first_column): (RowNum, ColNum),
+        (last_row, last_column): (RowNum, ColNum),
+        format: FormatId,
+    ) -> RangeLayer {
+        RangeLayer {
+            first_row: first_row.min(last_row),
+            first_column: first_column.min(last_column),
+            last_row: first_row.max(last_row),
+            last_column: first_column.max(last_column),
+            format,
+        }
+    }
+
+    fn contains(&self, row: RowNum, column: ColNum) -> bool {
+        (self.first_row..=self.last_row).contains(&row)
+            && (self.first_column..=self.last_column).contains(&column)
+    }
+}
+
+/// The `FormatLayers` keeps every format applied to the columns, rows, ranges
+/// and cells of a worksheet.
+///
+/// Excel stores a single format per cell, so a cell format replaces the row or
+/// column format instead of adding to it. The layers are combined whenever a cell
+/// covered by a layer is written, with every option of a more specific layer taking
+/// precedence: column formats, then row formats, then range formats in the order they
+/// were applied, then the format of a blank cell written with `write_blank`, then the
+/// format written to the cell itself, then the formats added to the cell with
+/// `add_to_range` and `add_to_cells`, such as the borders of `draw_border`.
+///
+/// Range layers are not written to every cell of the range, which may span millions of
+/// cells. They are combined into the cells already written when they are applied, and into
+/// the cells written later, while the empty cells of the range are left empty.
+///
+/// Below every layer is the default format of the cell value, such as the
+/// number format of a date. Writing a cell again replaces both its value format
+/// and its own format, while its blank format and the formats added with
+/// `add_to_cells` are kept.
+///
+/// A constant memory worksheet is written row by row, so the cells of a row can
+/// only be formatted while it is the current row. The layers of the rows below are
+/// applied when they become the current row, and the rows above are forgotten.
+#[derive(Default)]
+pub struct FormatLayers {
+    values: HashMap<(RowNum, ColNum), FormatId>,
+    columns: HashMap<ColNum, FormatId>,
+    rows: HashMap<RowNum, FormatId>,
+    ranges: Vec<RangeLayer>,
+    blanks: HashMap<(RowNum, ColNum), FormatId>,
+    cells: HashMap<(RowNum, ColNum), FormatId>,
+    added_ranges: Vec<RangeLayer>,
+    added: HashMap<(RowNum, ColNum), FormatId>,
+    /// The first and last column written in every row, indexed by row.
+    spans: Vec<Option<(ColNum, ColNum)>>,
+    /// The current row of a constant memory worksheet, or `None` for other worksheets.
+    current_row: Option<RowNum>,
+}
+
+impl FormatLayers {
+    /// Creates the layers of a constant memory worksheet.
+    pub fn with_constant_memory() -> FormatLayers {
+        FormatLayers {
+            current_row: Some(0),
+            ..FormatLayers::default()
+        }
+    }
+
+    /// Replaces the format written to a cell with `format`, and returns the combined
+    /// format of the cell, or `None` if the cell has no format at all.
+    ///
+    /// `value_format` is the default format of the value written to the cell, if any.
+    /// The layers are only combined if a column, row, range, blank or added format covers the cell.
+    ///
+    /// On a constant memory worksheet, the cell becomes part of the current row, and a
+    /// `RowOrderError` is raised if its row is above the current row.
+    pub fn write_cell(
+        &mut self,
+        worksheet: &mut Worksheet,
+        formats: &mut FormatRegistry,
+        row: RowNum,
+        column: ColNum,
+        value_format: Option<FormatId>,
+        format: Option<FormatId>,
+    ) -> PyResult<Option<FormatId>> {
+        self.advance_to(worksheet, formats, row, column)?;
+        self.mark_written(row, column);
+        match value_format {
+            Some(value_format) => {
+                self.values.insert((row, column), value_format);
+            }
+            None if !self.values.is_empty() => {
+                self.values.remove(&(row, column));
+            }
+            None => {}
+        }
+        match format {
+            Some(format) => {
+                self.cells.insert((row, column), format);
+            }
+            None if !self.cells.is_empty() => {
+                self.cells.remove(&(row, column));
+            }
+            None => {}
+        }
+        if !self.is_layered(row, column) {
+            return Ok(match (value_format, format) {
+                (Some(value_format), Some(format)) => Some(formats.overlay(value_format, format)),
+                (value_format, format) => format.or(value_format),
+            });
+        }
+        Ok(self.combined(formats, row, column))
+    }
+
+    /// Replaces the blank format of a cell with `format`, and returns the combined format
+    /// of the cell. The value and the format written to the cell before are cleared, while
+    /// the blank format is kept below the format of the values written to the cell later.
+    pub fn write_blank(
+        &mut self,
+        worksheet: &mut Worksheet,
+        formats: &mut FormatRegistry,
+        row: RowNum,
+        column: ColNum,
+        format: FormatId,
+    ) -> PyResult<FormatId> {
+        self.advance_to(worksheet, formats, row, column)?;
+        self.mark_written(row, column);
+        self.values.remove(&(row, column));
+        self.cells.remove(&(row, column));
+        self.blanks.insert((row, column), format);
+        Ok(self.combined(formats, row, column).unwrap_or(format))
+    }
+
+    /// Adds `format` to the format of a row, and updates the cells of the row
+    /// that have a format of their own.
+    pub fn set_row(
+        &mut self,
+        worksheet: &mut Worksheet,
+        formats: &mut FormatRegistry,
+        row: RowNum,
+        format: FormatId,
+    ) -> PyResult<()> {
+        check_limits(worksheet, row, 0)?;
+        self.check_row_order(worksheet, row, 0)?;
+        let format = add_layer(&mut self.rows, formats, row, format);
+        let result = worksheet
+            .set_row_format(row, formats.format(format))
+            .map(|_| ());
+        result.map_err(|error| errors::to_py_err(error, Some(worksheet.name().as_str()), None))?;
+
+        self.refresh_row(worksheet, formats, row)
+    }
+
+    /// Adds `format` to the format of a column, and updates the cells of the
+    /// column that have a format of their own.
+    pub fn set_column(
+        &mut self,
+        worksheet: &mut Worksheet,
+        formats: &mut FormatRegistry,
+        column: ColNum,
+        format: FormatId,
+    ) -> PyResult<()> {
+        check_limits(worksheet, 0, column)?;
+        let format = add_layer(&mut self.columns, formats, column, format);
+        let result = worksheet
+            .set_column_format(column, formats.format(format))
+            .map(|_| ());
+        result.map_err(|error| errors::to_py_err(error, Some(worksheet.name().as_str()), None))?;
+
+        // Empty cells take the column format, every other cell needs the combined format.
+        let layers = &*self;
+        let rows: BTreeSet<RowNum> = layers
+            .cells
+            .keys()
+            .chain(layers.values.keys())
+            .chain(layers.blanks.keys())
+            .chain(layers.added.keys())
+            .filter(|(_, cell_column)| *cell_column == column)
+            .map(|(row, _)| *row)
+            .chain(layers.rows.keys().copied())
+            .chain(
+                layers
+                    .ranges
+                    .iter()
+                    .chain(&layers.added_ranges)
+                    .filter(|range| (range.first_column..=range.last_column).contains(&column))
+                    .flat_map(|range| {
+                        layers
+                            .written_rows(range.first_row..=range.last_row)
+                            .filter(move |row| {
+                                layers.written_columns(*row, range).contains(&column)
+                            })
+                    }),
+            )
+            .collect();
+        for row in rows {
+            self.refresh(worksheet, formats, row, column)?;
+        }
+        Ok(())
+    }
+
+    /// Adds `format` on top of the formats of a range, below the formats of its cells,
+    /// and updates the cells of the range that are already written.
+    pub fn set_range(
+        &mut self,
+        worksheet: &mut Worksheet,
+        formats: &mut FormatRegistry,
+        (first_row, first_column): (RowNum, ColNum),
+        (last_row, last_column): (RowNum, ColNum),
+        format: FormatId,
+    ) -> PyResult<()> {
+        check_limits(
+            worksheet,
+            first_row.max(last_row),
+            first_column.max(last_column),
+        )?;
+        self.check_row_order(
+            worksheet,
+            first_row.min(last_row),
+            first_column.min(last_column),
+        )?;
+        let range = RangeLayer::new((first_row, first_column), (last_row, last_column), format);
+        self.ranges.push(range);
+        self.refresh_range(worksheet, formats, self.ranges.last().unwrap())
+    }
+
+    /// Adds `format` on top of the formats written to the cells of a range, so that it
+    /// overrides the same options of the cells, even when they are written again.
+    /// Only the cells of the range that are already written are updated.
+    pub fn add_to_range(
+        &mut self,
+        worksheet: &mut Worksheet,
+        formats: &mut FormatRegistry,
+        (first_row, first_column): (RowNum, ColNum),
+        (last_row, last_column): (RowNum, ColNum),
+        format: FormatId,
+    ) -> PyResult<()> {
+        check_limits(worksheet, last_row, last_column)?;
+        self.check_row_order(worksheet, first_row, first_column)?;
+        let range = RangeLayer::new((first_row, first_column), (last_row, last_column), format);
+        self.added_ranges.push(range);
+        self.refresh_range(worksheet, formats, self.added_ranges.last().unwrap())
+    }
+
+    /// Adds `format` on top of the formats written to every cell of a range, like
+    /// `add_to_range`, and makes the empty cells of the range blank cells with the combined
+    /// format. Every cell of the range is visited, so this is meant for a single row or column.
+    pub fn add_to_cells(
+        &mut self,
+        worksheet: &mut Worksheet,
+        formats: &mut FormatRegistry,
+        (first_row, first_column): (RowNum, ColNum),
+        (last_row, last_column): (RowNum, ColNum),
+        format: FormatId,
+    ) -> PyResult<()> {
+        check_limits(worksheet, last_row, last_column)?;
+        self.check_row_order(worksheet, first_row, first_column)?;
+        for row in first_row..=last_row {
+            for column in first_column..=last_column {
+                add_layer(&mut self.added, formats, (row, column), format);
+                self.refresh(worksheet, formats, row, column)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the layers of the rows left to write on a constant memory worksheet, at the end
+    /// of the worksheet, since they can no longer be formatted once the workbook is saved.
+    pub fn finish(
+        &mut self,
+        worksheet: &mut Worksheet,
+        formats: &mut FormatRegistry,
+    ) -> PyResult<()> {
+        let Some(current_row) = self.current_row else {
+            return Ok(());
+        };
+        let last_row = self
+            .rows
+            .keys()
+            .copied()
+            .chain(
+                self.cells
+                    .keys()
+                    .chain(self.blanks.keys())
+                    .chain(self.added.keys())
+                    .map(|(row, _)| *row),
+            )
+            .max()
+            .unwrap_or(current_row);
+        self.advance_to(worksheet, formats, last_row, 0)
+    }
+
+    /// Moves the current row of a constant memory worksheet down to `row`, applying the
+    /// layers of the rows in between first, so that their empty cells are formatted too.
+    fn advance_to(
+        &mut self,
+        worksheet: &mut Worksheet,
+        formats: &mut FormatRegistry,
+        row: RowNum,
+        column: ColNum,
+    ) -> PyResult<()> {
+        let Some(current_row) = self.current_row else {
+            return Ok(());
+        };
+        self.check_row_order(worksheet, row, column)?;
+        if row == current_row {
+            return Ok(());
+        }
+        let next_rows = current_row + 1..=row;
+        let rows: BTreeSet<RowNum> = self
+            .rows
+            .keys()
+            .copied()
+            .chain(
+                self.cells
+                    .keys()
+                    .chain(self.blanks.keys())
+                    .chain(self.added.keys())
+                    .map(|(row, _)| *row),
+            )
+            .filter(|row| next_rows.contains(row))
+            .collect();
+        // The cells of these rows are not written yet, so their formats are written as blank
+        // cells, which also moves the worksheet to each row in order.
+        for pending_row in rows {
+            for column in self.layered_columns(pending_row) {
+                let Some(format) = self.combined(formats, pending_row, column) else {
+                    continue;
+                };
+                let result = worksheet
+                    .write_blank(pending_row, column, formats.format(format))
+                    .map(|_| ());
+                result.map_err(|error| {
+                    errors::to_py_err(
+                        error,
+                        Some(worksheet.name().as_str()),
+                        Some((pending_row, column)),
+                    )
+                })?;
+            }
+        }
+        self.current_row = Some(row);
+
+        // The rows above are written to disk, so their layers are no longer needed.
+        self.values.retain(|(cell_row, _), _| *cell_row >= row);
+        self.blanks.retain(|(cell_row, _), _| *cell_row >= row);
+        self.cells.retain(|(cell_row, _), _| *cell_row >= row);
+        self.added.retain(|(cell_row, _), _| *cell_row >= row);
+        self.rows.retain(|layer_row, _| *layer_row >= row);
+        self.ranges.retain(|range| range.last_row >= row);
+        self.added_ranges.retain(|range| range.last_row >= row);
+        Ok(())
+    }
+
+    /// Raises a `RowOrderError` if a cell is above the current row of a constant memory worksheet.
+    fn check_row_order(&self, worksheet: &Worksheet, row: RowNum, column: ColNum) -> PyResult<()> {
+        match self.current_row {
+            Some(current_row) if row < current_row => Err(errors::new_err::<RowOrderError>(
+                format!(
+                    "Cannot write to row {row}, above the current row {current_row} of a constant memory worksheet, since the rows above it are already written to disk. Write the rows in order."
+                ),
+                Some(worksheet.name().as_str()),
+                Some((row, column)),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Writes the combined format of the layered cells of a row. Empty cells take the row format.
+    fn refresh_row(
+        &self,
+        worksheet: &mut Worksheet,
+        formats: &mut FormatRegistry,
+        row: RowNum,
+    ) -> PyResult<()> {
+        for column in self.layered_columns(row) {
+            self.refresh(worksheet, formats, row, column)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the columns of a row whose cells have a format of their own, where a
+    /// column format meets the row format, or that are written and covered by a range.
+    fn layered_columns(&self, row: RowNum) -> BTreeSet<ColNum> {
+        let row_columns = self
+            .rows
+            .contains_key(&row)
+            .then(|| self.columns.keys().copied())
+            .into_iter()
+            .flatten();
+        self.cells
+            .keys()
+            .chain(self.values.keys())
+            .chain(self.blanks.keys())
+            .chain(self.added.keys())
+            .filter(|(cell_row, _)| *cell_row == row)
+            .map(|(_, column)| *column)
+            .chain(row_columns)
+            .chain(
+                self.ranges
+                    .iter()
+                    .chain(&self.added_ranges)
+                    .filter(|range| (range.first_row..=range.last_row).contains(&row))
+                    .flat_map(|range| self.written_columns(row, range)),
+            )
+            .collect()
+    }
+
+    /// Writes the combined format of the cells of a range that are already written.
+    fn refresh_range(
+        &self,
+        worksheet: &mut Worksheet,
+        formats: &mut FormatRegistry,
+        range: &RangeLayer,
+    ) -> PyResult<()> {
+        let rows = match self.current_row {
+            // Only the current row of a constant memory worksheet can be updated.
+            Some(current_row) => current_row.max(range.first_row)..=current_row.min(range.last_row),
+            None => range.first_row..=range.last_row,
+        };
+        for row in self.written_rows(rows.clone()) {
+            for column in self.written_columns(row, range) {
+                self.refresh(worksheet, formats, row, column)?;
+            }
+        }
+        // The blank cells of `add_to_cells` are not written cells, but need the range too.
+        for &(row, column) in self.added.keys() {
+            if rows.contains(&row) && range.contains(row, column) {
+                self.refresh(worksheet, formats, row, column)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Records that a cell is written, so that the ranges applied later update it.
+    fn mark_written(&mut self, row: RowNum, column: ColNum) {
+        let index = row as usize;
+        if self.spans.len() <= index {
+            self.spans.resize(index + 1, None);
+        }
+        let span = &mut self.spans[index];
+        *span = Some(match *span {
+            Some((first, last)) => (first.min(column), last.max(column)),
+            None => (column, column),
+        });
+    }
+
+    /// Returns the rows of `rows` that have written cells.
+    fn written_rows(&self, rows: RangeInclusive<RowNum>) -> impl Iterator<Item = RowNum> + '_ {
+        let end = (*rows.end()).min(self.spans.len() as RowNum);
+        (*rows.start()..=end)
+            .filter(|row| self.spans.get(*row as usize).is_some_and(Option::is_some))
+    }
+
+    /// Returns the columns of a range between the first and last written cells of a row.
+    fn written_columns(&self, row: RowNum, range: &RangeLayer) -> RangeInclusive<ColNum> {
+        match self.spans.get(row as usize) {
+            Some(&Some((first, last))) => {
+                range.first_column.max(first)..=range.last_column.min(last)
+            }
+            // An empty range, since the last column is before the first one.
+            _ => range.last_column + 1..=range.last_column,
+        }
+    }
+
+    /// Returns the format combining every layer that applies to a cell.
+    fn combined(
+        &self,
+        formats: &mut FormatRegistry,
+        row: RowNum,
+        column: ColNum,
+    ) -> Option<FormatId> {
+        let ranges = self
+            .ranges
+            .iter()
+            .filter(|range| range.contains(row, column))
+            .map(|range| range.format);
+        let added_ranges = self
+            .added_ranges
+            .iter()
+            .filter(|range| range.contains(row, column))
+            .map(|range| range.format);
+        [
+            self.values.get(&(row, column)),
+            self.columns.get(&column),
+            self.rows.get(&row),
+        ]
+        .into_iter()
+        .flatten()
+        .copied()
+        .chain(ranges)
+        .chain(self.blanks.get(&(row, column)).copied())
+        .chain(self.cells.get(&(row, column)).copied())
+        .chain(added_ranges)
+        .chain(self.added.get(&(row, column)).copied())
+        .reduce(|base, other| formats.overlay(base, other))
+    }
+
+    /// Returns whether a column, row, range, blank or added format covers a cell.
+    fn is_layered(&self, row: RowNum, column: ColNum) -> bool {
+        self.columns.contains_key(&column)
+            || self.rows.contains_key(&row)
+            || self.blanks.contains_key(&(row, column))
+            || self.added.contains_key(&(row, column))
+            || self
+                .ranges
+                .iter()
+                .chain(&self.added_ranges)
+                .any(|range| range.contains(row, column))
+    }
+
+    /// Writes the combined format of a cell, without changing its value.
+    ///
+    /// On a constant memory worksheet, only the current row is written. The rows above
+    /// are already written to disk, and the rows below are refreshed once they are reached.
+    fn refresh(
+        &self,
+        worksheet: &mut Worksheet,
+        formats: &mut FormatRegistry,
+        row: RowNum,
+        column: ColNum,
+    ) -> PyResult<()> {
+        if self
+            .current_row
+            .is_some_and(|current_row| row != current_row)
+        {
+            return Ok(());
+        }
+        let Some(format) = self.combined(formats, row, column) else {
+            return Ok(());
+        };
+        let result = worksheet
+            .set_cell_format(row, column, formats.format(format))
+            .map(|_| ());
+        result.map_err(|error| {
+            errors::to_py_err(error, Some(worksheet.name().as_str()), Some((row, column)))
+        })
+    }
+}
+
+/// Adds `format` on top of the format stored under `key`, and returns the result.
+fn add_layer<K: std::hash::Hash + Eq>(
+    layer: &mut HashMap<K, FormatId>,
+    formats: &mut FormatRegistry,
+    key: K,
+    format: FormatId,
+) -> FormatId {
+    let format = match layer.get(&key) {
+        Some(&previous) => formats.overlay(previous, format),
+        None => format,
+    };
+    layer.insert(key, format);
+    format
+}
+
+/// Checks that a cell is inside the worksheet limits, before a layer is added for it.
+fn check_limits(worksheet: &Worksheet, row: RowNum, column: ColNum) -> PyResult<()> {
+    if row >= ROW_MAX || column >= COL_MAX {
+        return Err(errors::to_py_err(
+            XlsxError::RowColumnLimitError,
+            Some(worksheet.name().as_str()),
+            Some((row, column)),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::{BorderOption, ExcelFormat};
+
+    fn thin_top() -> ExcelFormat {
+        let thin = BorderOption::Style("thin".to_string());
+        ExcelFormat::borders(&thin, None, [true, false, false, false])
+    }
+
+    #[test]
+    fn combined_applies_layers_in_precedence_order() {
+        let mut formats = FormatRegistry::default();
+        let mut layers = FormatLayers::default();
+        let mut worksheet = Worksheet::new();
+        let column = formats.id(&ExcelFormat::with_num_format("0.00".to_string()));
+        let row = formats.id(&ExcelFormat::with_num_format("0.000".to_string()));
+        let cell = formats.id(&ExcelFormat::hyperlink());
+        let border = formats.id(&thin_top());
+
+        layers
+            .set_column(&mut worksheet, &mut formats, 1, column)
+            .unwrap();
+        assert_eq!(layers.combined(&mut formats, 0, 1), Some(column));
+        layers
+            .set_row(&mut worksheet, &mut formats, 0, row)
+            .unwrap();
+        assert_eq!(layers.combined(&mut formats, 0, 1), Some(row));
+        assert_eq!(layers.combined(&mut formats, 1, 0), None);
+
+        layers
+            .add_to_cells(&mut worksheet, &mut formats, (0, 1), (0, 1), border)
+            .unwrap();
+        let combined = layers
+            .write_cell(&mut worksheet, &mut formats, 0, 1, None, Some(cell))
+            .unwrap()
+            .unwrap();
+        let expected = ExcelFormat::with_num_format("0.000".to_string())
+            .overlay(&ExcelFormat::hyperlink())
+            .overlay(&thin_top());
+        assert!(formats.options(combined) == &expected);
+    }
+
+    #[test]
+    fn writing_a_cell_again_replaces_its_format() {
+        let mut formats = FormatRegistry::default();
+        let mut layers = FormatLayers::default();
+        let mut worksheet = Worksheet::new();
+        let hyperlink = formats.id(&ExcelFormat::hyperlink());
+        let border = formats.id(&thin_top());
+
+        for format in [Some(hyperlink), Some(border), None] {
+            let written = layers
+                .write_cell(&mut worksheet, &mut formats, 0, 0, None, format)
+                .unwrap();
+            assert_eq!(written, format);
+        }
+    }
+
+    #[test]
+    fn blank_formats_are_kept_below_later_writes() {
+        let mut formats = FormatRegistry::default();
+        let mut layers = FormatLayers::default();
+        let mut worksheet = Worksheet::new();
+        let border = formats.id(&thin_top());
+        let hyperlink = formats.id(&ExcelFormat::hyperlink());
+
+        let blank = layers
+            .write_blank(&mut worksheet, &mut formats, 0, 0, border)
+            .unwrap();
+        assert_eq!(blank, border);
+        let written = layers
+            .write_cell(&mut worksheet, &mut formats, 0, 0, None, Some(hyperlink))
+            .unwrap()
+            .unwrap();
+        let expected = thin_top().overlay(&ExcelFormat::hyperlink());
+        assert!(formats.options(written) == &expected);
+        let rewritten = layers
+            .write_cell(&mut worksheet, &mut formats, 0, 0, None, None)
+            .unwrap();
+        assert_eq!(rewritten, Some(border));
+    }
+
+    #[test]
+    fn range_formats_only_update_written_cells() {
+        let mut formats = FormatRegistry::default();
+        let mut layers = FormatLayers::default();
+        let mut worksheet = Worksheet::new();
+        let shading = formats.id(&ExcelFormat::with_num_format("0.00".to_string()));
+        let border = formats.id(&thin_top());
+
+        layers
+            .write_cell(&mut worksheet, &mut formats, 2, 1, None, None)
+            .unwrap();
+        layers
+            .set_range(
+                &mut worksheet,
+                &mut formats,
+                (0, 0),
+                (ROW_MAX - 1, 5),
+                shading,
+            )
+            .unwrap();
+        layers
+            .add_to_range(
+                &mut worksheet,
+                &mut formats,
+                (0, 0),
+                (ROW_MAX - 1, 5),
+                border,
+            )
+            .unwrap();
+        assert_eq!(layers.layered_columns(2), BTreeSet::from([1]));
+        assert!(layers.layered_columns(3).is_empty());
+
+        let written = layers
+            .write_cell(&mut worksheet, &mut formats, 500_000, 6, None, None)
+            .unwrap();
+        assert_eq!(written, None);
+        let written = layers
+            .write_cell(&mut worksheet, &mut formats, 500_000, 5, None, None)
+            .unwrap()
+            .unwrap();
+        let expected = ExcelFormat::with_num_format("0.00".to_string()).overlay(&thin_top());
+        assert!(formats.options(written) == &expected);
+    }
+
+    #[test]
+    fn constant_memory_applies_ranges_to_the_rows_written() {
+        let mut formats = FormatRegistry::default();
+        let mut layers = FormatLayers::with_constant_memory();
+        let mut worksheet = Worksheet::new();
+        let shading = formats.id(&ExcelFormat::with_num_format("0.00".to_string()));
+
+        layers
+            .set_range(
+                &mut worksheet,
+                &mut formats,
+                (0, 0),
+                (ROW_MAX - 1, 5),
+                shading,
+            )
+            .unwrap();
+        let written = layers
+            .write_cell(&mut worksheet, &mut formats, ROW_MAX - 1, 0, None, None)
+            .unwrap();
+        assert_eq!(written, Some(shading));
+        layers.finish(&mut worksheet, &mut formats).unwrap();
+    }
+
+    #[test]
+    fn constant_memory_rejects_rows_above_the_current_row() {
+        let mut formats = FormatRegistry::default();
+        let mut layers = FormatLayers::with_constant_memory();
+        let mut worksheet = Worksheet::new();
+        layers
+            .write_cell(&mut worksheet, &mut formats, 5, 0, None, None)
+            .unwrap();
+        pyo3::prepare_freethreaded_python();
+        assert!(layers
+            .write_cell(&mut worksheet, &mut formats, 4, 0, None, None)
+            .is_err());
+    }
+}
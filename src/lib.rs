@@ -1,16 +1,32 @@
+pub mod array;
+pub mod dataframe;
+pub mod datetime;
+pub mod error_value;
+pub mod errors;
 pub mod format;
+pub mod layers;
+pub mod number;
+pub mod protection;
+pub mod rollover;
 pub mod util;
 pub mod workbook;
 pub mod writer;
 
+use error_value::ExcelErrorValue;
 use format::ExcelFormat;
+use protection::ExcelProtectionOptions;
 use pyo3::prelude::*;
 use workbook::ExcelWorkbook;
+use writer::StringOverflowRecord;
 
 /// A Python module implemented in Rust.
 #[pymodule]
 fn pyaccelsx(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<ExcelWorkbook>()?;
     m.add_class::<ExcelFormat>()?;
+    m.add_class::<ExcelProtectionOptions>()?;
+    m.add_class::<ExcelErrorValue>()?;
+    m.add_class::<StringOverflowRecord>()?;
+    errors::register(m)?;
     Ok(())
 }
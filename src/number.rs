@@ -0,0 +1,241 @@
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::intern;
+use pyo3::prelude::*;
+use pyo3::sync::GILOnceCell;
+use pyo3::types::{PyInt, PyType};
+
+/// The largest integer magnitude that a `f64`, and so an Excel number, holds exactly.
+const MAX_EXACT_INT: u64 = 1 << 53;
+
+static DECIMAL: GILOnceCell<Py<PyType>> = GILOnceCell::new();
+
+/// The policy applied to integers beyond 2^53, which Excel cannot store exactly.
+///
+/// Accepted values from Python are `"text"`, `"number"` and `"error"`.
+#[derive(Clone, Copy, PartialEq)]
+pub enum IntOverflowPolicy {
+    /// Write the integer as a string, keeping every digit.
+    Text,
+    /// Write the nearest number, and emit a `UserWarning`.
+    Number,
+    /// Raise a `ParameterError`.
+    Error,
+}
+
+impl<'py> FromPyObject<'py> for IntOverflowPolicy {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        match ob.extract::<&str>()? {
+            "text" => Ok(IntOverflowPolicy::Text),
+            "number" => Ok(IntOverflowPolicy::Number),
+            "error" => Ok(IntOverflowPolicy::Error),
+            other => Err(PyValueError::new_err(format!(
+                "Unknown int_overflow '{other}'. Expected one of: 'text', 'number', 'error'."
+            ))),
+        }
+    }
+}
+
+/// A Python `int`, or an integer scalar such as `numpy.int64`, extracted without going through `f64`.
+#[derive(Clone)]
+pub enum IntValue {
+    Small(i64),
+    /// An integer beyond the range of `i64`, as its decimal digits.
+    Big(String),
+}
+
+impl<'py> FromPyObject<'py> for IntValue {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        // Check the type first, so that other numbers fail without raising an `OverflowError`.
+        // Other integer types, such as `numpy.int64`, are converted with `__index__`.
+        let int = match ob.downcast::<PyInt>() {
+            Ok(int) => int.clone(),
+            Err(error) => {
+                if !ob.get_type().hasattr(intern!(ob.py(), "__index__"))? {
+                    return Err(error.into());
+                }
+                ob.call_method0(intern!(ob.py(), "__index__"))?
+                    .downcast_into::<PyInt>()?
+            }
+        };
+        match int.extract::<i64>() {
+            Ok(value) => Ok(IntValue::Small(value)),
+            Err(_) => Ok(IntValue::Big(int.str()?.to_string())),
+        }
+    }
+}
+
+impl IntValue {
+    /// Returns the integer as a number if Excel can store it exactly.
+    pub fn to_exact_f64(&self) -> Option<f64> {
+        match self {
+            IntValue::Small(value) if value.unsigned_abs() <= MAX_EXACT_INT => Some(*value as f64),
+            _ => None,
+        }
+    }
+
+    /// Returns the decimal digits of the integer.
+    pub fn to_text(&self) -> String {
+        match self {
+            IntValue::Small(value) => value.to_string(),
+            IntValue::Big(digits) => digits.clone(),
+        }
+    }
+}
+
+/// The rounding applied to `decimal.Decimal` values before they are written as numbers.
+///
+/// Accepted rounding modes from Python are `"half_even"`, `"half_up"`, `"half_down"`,
+/// `"up"`, `"down"`, `"ceiling"`, `"floor"` and `"05up"`, the same as the `decimal` module.
+#[derive(Clone, Copy)]
+pub struct DecimalRounding {
+    pub places: Option<i32>,
+    pub mode: DecimalRoundingMode,
+}
+
+#[derive(Clone, Copy)]
+pub struct DecimalRoundingMode(&'static str);
+
+impl<'py> FromPyObject<'py> for DecimalRoundingMode {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        match ob.extract::<&str>()? {
+            "half_even" => Ok(DecimalRoundingMode("ROUND_HALF_EVEN")),
+            "half_up" => Ok(DecimalRoundingMode("ROUND_HALF_UP")),
+            "half_down" => Ok(DecimalRoundingMode("ROUND_HALF_DOWN")),
+            "up" => Ok(DecimalRoundingMode("ROUND_UP")),
+            "down" => Ok(DecimalRoundingMode("ROUND_DOWN")),
+            "ceiling" => Ok(DecimalRoundingMode("ROUND_CEILING")),
+            "floor" => Ok(DecimalRoundingMode("ROUND_FLOOR")),
+            "05up" => Ok(DecimalRoundingMode("ROUND_05UP")),
+            other => Err(PyValueError::new_err(format!(
+                "Unknown decimal_rounding '{other}'. Expected one of: 'half_even', 'half_up', 'half_down', 'up', 'down', 'ceiling', 'floor', '05up'."
+            ))),
+        }
+    }
+}
+
+impl DecimalRoundingMode {
+    pub const HALF_EVEN: DecimalRoundingMode = DecimalRoundingMode("ROUND_HALF_EVEN");
+}
+
+/// A `decimal.Decimal` passed from Python.
+pub struct DecimalValue(Py<PyAny>);
+
+impl Clone for DecimalValue {
+    fn clone(&self) -> Self {
+        Python::with_gil(|py| DecimalValue(self.0.clone_ref(py)))
+    }
+}
+
+impl<'py> FromPyObject<'py> for DecimalValue {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        let decimal = DECIMAL.import(ob.py(), "decimal", "Decimal")?;
+        if !ob.is_instance(decimal)? {
+            return Err(PyTypeError::new_err("expected a decimal.Decimal"));
+        }
+        Ok(DecimalValue(ob.clone().unbind()))
+    }
+}
+
+impl DecimalValue {
+    /// Returns the value rounded to the configured number of decimal places, as a number.
+    pub fn to_f64(&self, rounding: DecimalRounding) -> PyResult<f64> {
+        Python::with_gil(|py| {
+            let value = self.0.bind(py);
+            let Some(places) = rounding.places else {
+                return value.extract();
+            };
+            let decimal = DECIMAL.import(py, "decimal", "Decimal")?;
+            let exponent = decimal.call1((format!("1e{}", -places),))?;
+            value
+                .call_method1("quantize", (exponent, rounding.mode.0))?
+                .extract()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn int_value_is_exact_up_to_2_53() {
+        assert_eq!(
+            IntValue::Small(1 << 53).to_exact_f64(),
+            Some(9_007_199_254_740_992.0)
+        );
+        assert_eq!(
+            IntValue::Small(-(1 << 53)).to_exact_f64(),
+            Some(-9_007_199_254_740_992.0)
+        );
+        assert_eq!(IntValue::Small((1 << 53) + 1).to_exact_f64(), None);
+        assert_eq!(IntValue::Small(i64::MIN).to_exact_f64(), None);
+        assert_eq!(
+            IntValue::Big("18446744073709551616".to_string()).to_exact_f64(),
+            None
+        );
+    }
+
+    #[test]
+    fn int_value_text_keeps_every_digit() {
+        assert_eq!(IntValue::Small(-42).to_text(), "-42");
+        assert_eq!(IntValue::Small((1 << 53) + 1).to_text(), "9007199254740993");
+    }
+
+    #[test]
+    fn int_value_extracts_big_integers_as_digits() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let big = py.eval(c"2 ** 70", None, None).unwrap();
+            match big.extract::<IntValue>().unwrap() {
+                IntValue::Big(digits) => assert_eq!(digits, "1180591620717411303424"),
+                IntValue::Small(_) => panic!("expected a big integer"),
+            }
+            let small = py.eval(c"-7", None, None).unwrap();
+            assert!(matches!(
+                small.extract::<IntValue>().unwrap(),
+                IntValue::Small(-7)
+            ));
+            assert!(py
+                .eval(c"1.5", None, None)
+                .unwrap()
+                .extract::<IntValue>()
+                .is_err());
+        });
+    }
+
+    #[test]
+    fn decimal_value_is_rounded_before_conversion() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let value = py
+                .eval(c"__import__('decimal').Decimal('2.675')", None, None)
+                .unwrap()
+                .extract::<DecimalValue>()
+                .unwrap();
+            let rounding = |places, mode| DecimalRounding { places, mode };
+            assert_eq!(
+                value
+                    .to_f64(rounding(None, DecimalRoundingMode::HALF_EVEN))
+                    .unwrap(),
+                2.675
+            );
+            assert_eq!(
+                value
+                    .to_f64(rounding(Some(2), DecimalRoundingMode::HALF_EVEN))
+                    .unwrap(),
+                2.68
+            );
+            assert_eq!(
+                value
+                    .to_f64(rounding(Some(2), DecimalRoundingMode("ROUND_DOWN")))
+                    .unwrap(),
+                2.67
+            );
+            assert!(py
+                .eval(c"2.675", None, None)
+                .unwrap()
+                .extract::<DecimalValue>()
+                .is_err());
+        });
+    }
+}
@@ -0,0 +1,128 @@
+/// This module contains the worksheet protection options.
+use pyo3::prelude::*;
+use rust_xlsxwriter::ProtectionOptions;
+
+/// The `ExcelProtectionOptions` contains the actions that users are still
+/// allowed to perform on a protected worksheet. It is passed from Python to
+/// `ExcelWorkbook.protect_worksheet`, and the defaults match Excel's defaults.
+///
+/// ## Examples
+/// The following example demonstrates protecting a worksheet while still
+/// allowing sorting and filtering.
+/// ```
+/// from pyaccelsx import ExcelWorkbook, ExcelProtectionOptions
+///
+/// def main():
+///     workbook = ExcelWorkbook()
+///     workbook.add_worksheet()
+///
+///     options = ExcelProtectionOptions(sort=True, use_autofilter=True)
+///     workbook.protect_worksheet(password="secret", options=options)
+///
+///     workbook.save("example.xlsx")
+/// ```
+#[pyclass(get_all, set_all)]
+#[derive(Clone)]
+pub struct ExcelProtectionOptions {
+    select_locked_cells: bool,
+    select_unlocked_cells: bool,
+    format_cells: bool,
+    format_columns: bool,
+    format_rows: bool,
+    insert_columns: bool,
+    insert_rows: bool,
+    insert_links: bool,
+    delete_columns: bool,
+    delete_rows: bool,
+    sort: bool,
+    use_autofilter: bool,
+    use_pivot_tables: bool,
+    edit_scenarios: bool,
+    edit_objects: bool,
+    contents: bool,
+}
+
+#[pymethods]
+impl ExcelProtectionOptions {
+    #[new]
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (
+        *,
+        select_locked_cells=true,
+        select_unlocked_cells=true,
+        format_cells=false,
+        format_columns=false,
+        format_rows=false,
+        insert_columns=false,
+        insert_rows=false,
+        insert_links=false,
+        delete_columns=false,
+        delete_rows=false,
+        sort=false,
+        use_autofilter=false,
+        use_pivot_tables=false,
+        edit_scenarios=false,
+        edit_objects=false,
+        contents=true,
+    ))]
+    pub fn new(
+        select_locked_cells: bool,
+        select_unlocked_cells: bool,
+        format_cells: bool,
+        format_columns: bool,
+        format_rows: bool,
+        insert_columns: bool,
+        insert_rows: bool,
+        insert_links: bool,
+        delete_columns: bool,
+        delete_rows: bool,
+        sort: bool,
+        use_autofilter: bool,
+        use_pivot_tables: bool,
+        edit_scenarios: bool,
+        edit_objects: bool,
+        contents: bool,
+    ) -> ExcelProtectionOptions {
+        ExcelProtectionOptions {
+            select_locked_cells,
+            select_unlocked_cells,
+            format_cells,
+            format_columns,
+            format_rows,
+            insert_columns,
+            insert_rows,
+            insert_links,
+            delete_columns,
+            delete_rows,
+            sort,
+            use_autofilter,
+            use_pivot_tables,
+            edit_scenarios,
+            edit_objects,
+            contents,
+        }
+    }
+}
+
+impl From<&ExcelProtectionOptions> for ProtectionOptions {
+    fn from(options: &ExcelProtectionOptions) -> ProtectionOptions {
+        ProtectionOptions {
+            select_locked_cells: options.select_locked_cells,
+            select_unlocked_cells: options.select_unlocked_cells,
+            format_cells: options.format_cells,
+            format_columns: options.format_columns,
+            format_rows: options.format_rows,
+            insert_columns: options.insert_columns,
+            insert_rows: options.insert_rows,
+            insert_links: options.insert_links,
+            delete_columns: options.delete_columns,
+            delete_rows: options.delete_rows,
+            sort: options.sort,
+            use_autofilter: options.use_autofilter,
+            use_pivot_tables: options.use_pivot_tables,
+            edit_scenarios: options.edit_scenarios,
+            edit_objects: options.edit_objects,
+            contents: options.contents,
+        }
+    }
+}
@@ -0,0 +1,338 @@
+/// This module contains the continuation of a worksheet on new worksheets once
+/// it reaches Excel's row limit.
+use std::ops::Range;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rust_xlsxwriter::{ColNum, Formula, RowNum};
+
+use crate::format::FormatId;
+use crate::layers::ROW_MAX;
+use crate::util::ValueType;
+use crate::workbook::ValueOverrides;
+
+/// The maximum length of a worksheet name in Excel.
+const SHEET_NAME_MAX: usize = 31;
+
+/// The policy applied to rows past the last row of a worksheet.
+///
+/// Accepted values from Python are `"error"` and `"new_sheet"`.
+#[derive(Clone, Copy, PartialEq)]
+pub enum SheetOverflowPolicy {
+    /// Raise a `RowColumnLimitError`.
+    Error,
+    /// Continue on a new worksheet, repeating the header rows and settings of the worksheet.
+    NewSheet,
+}
+
+impl<'py> FromPyObject<'py> for SheetOverflowPolicy {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        match ob.extract::<&str>()? {
+            "error" => Ok(SheetOverflowPolicy::Error),
+            "new_sheet" => Ok(SheetOverflowPolicy::NewSheet),
+            other => Err(PyValueError::new_err(format!(
+                "Unknown overflow '{other}'. Expected one of: 'error', 'new_sheet'."
+            ))),
+        }
+    }
+}
+
+/// A write to the header rows, written again at the top of every continuation worksheet.
+/// Ranges are kept as they were given, with `first` and `last` in either order.
+#[derive(Clone)]
+pub enum HeaderWrite {
+    Value {
+        row: RowNum,
+        column: ColNum,
+        value: Option<ValueType>,
+        overrides: ValueOverrides,
+        format: Option<FormatId>,
+    },
+    Blank {
+        row: RowNum,
+        column: ColNum,
+        format: FormatId,
+    },
+    Formula {
+        row: RowNum,
+        column: ColNum,
+        formula: Formula,
+        format: Option<FormatId>,
+    },
+    RangeFormula {
+        first: (RowNum, ColNum),
+        last: (RowNum, ColNum),
+        formula: Formula,
+        format: Option<FormatId>,
+        dynamic: bool,
+    },
+    Url {
+        row: RowNum,
+        column: ColNum,
+        url: String,
+        text: Option<String>,
+        tip: Option<String>,
+        format: Option<FormatId>,
+    },
+    Merge {
+        first: (RowNum, ColNum),
+        last: (RowNum, ColNum),
+        format: Option<FormatId>,
+    },
+    RowFormat {
+        row: RowNum,
+        format: FormatId,
+    },
+    RangeFormat {
+        first: (RowNum, ColNum),
+        last: (RowNum, ColNum),
+        format: FormatId,
+    },
+    /// A format added to the cells of a range by `draw_border`, or to the cells
+    /// written in the range if `lazy` is set.
+    AddedFormat {
+        first: (RowNum, ColNum),
+        last: (RowNum, ColNum),
+        format: FormatId,
+        lazy: bool,
+    },
+}
+
+impl HeaderWrite {
+    /// The first row written.
+    pub fn row(&self) -> RowNum {
+        match self {
+            HeaderWrite::Value { row, .. }
+            | HeaderWrite::Blank { row, .. }
+            | HeaderWrite::Formula { row, .. }
+            | HeaderWrite::Url { row, .. }
+            | HeaderWrite::RowFormat { row, .. } => *row,
+            HeaderWrite::RangeFormula { first, last, .. }
+            | HeaderWrite::Merge { first, last, .. }
+            | HeaderWrite::RangeFormat { first, last, .. }
+            | HeaderWrite::AddedFormat { first, last, .. } => first.0.min(last.0),
+        }
+    }
+
+    /// Returns the part of the write in the first `header_rows` rows, if any. Range formats
+    /// are cut at the last header row, while merges and array formulas are kept whole and
+    /// must fit in the header rows.
+    pub fn clip(self, header_rows: RowNum) -> Option<HeaderWrite> {
+        if self.row() >= header_rows {
+            return None;
+        }
+        let clip = |first: (RowNum, ColNum), last: (RowNum, ColNum)| {
+            let last_row = first.0.max(last.0).min(header_rows - 1);
+            ((first.0.min(last.0), first.1), (last_row, last.1))
+        };
+        Some(match self {
+            HeaderWrite::RangeFormat {
+                first,
+                last,
+                format,
+            } => {
+                let (first, last) = clip(first, last);
+                HeaderWrite::RangeFormat {
+                    first,
+                    last,
+                    format,
+                }
+            }
+            HeaderWrite::AddedFormat {
+                first,
+                last,
+                format,
+                lazy,
+            } => {
+                let (first, last) = clip(first, last);
+                HeaderWrite::AddedFormat {
+                    first,
+                    last,
+                    format,
+                    lazy,
+                }
+            }
+            write => write,
+        })
+    }
+}
+
+/// A continuation worksheet of a rollover.
+pub struct Continuation {
+    /// The index of the worksheet in the workbook.
+    pub index: usize,
+    /// The number of header writes already written to the worksheet.
+    pub replayed: usize,
+}
+
+/// The `Rollover` keeps what a worksheet with `overflow="new_sheet"` repeats on its
+/// continuation worksheets, such as "Data (2)" for the worksheet "Data".
+///
+/// Rows are written to the worksheet with their logical row index. The rows past
+/// Excel's limit are written to the continuation worksheets, below the header rows.
+pub struct Rollover {
+    name: String,
+    header_rows: Option<RowNum>,
+    /// The continuation worksheets, in order.
+    pub continuations: Vec<Continuation>,
+    /// The writes to the header rows, in the order they were made.
+    pub header_writes: Vec<HeaderWrite>,
+    pub column_formats: Vec<(ColNum, FormatId)>,
+    pub column_widths: Vec<(ColNum, f64)>,
+    pub freeze_panes: Option<(RowNum, ColNum)>,
+}
+
+impl Rollover {
+    /// Creates the rollover of a worksheet. Without `header_rows`, the header rows
+    /// are the rows above the frozen panes of the worksheet, if any.
+    pub fn new(name: String, header_rows: Option<RowNum>) -> Rollover {
+        Rollover {
+            name,
+            header_rows,
+            continuations: Vec::new(),
+            header_writes: Vec::new(),
+            column_formats: Vec::new(),
+            column_widths: Vec::new(),
+            freeze_panes: None,
+        }
+    }
+
+    /// The number of header rows repeated on every continuation worksheet.
+    pub fn header_rows(&self) -> RowNum {
+        self.header_rows
+            .or(self.freeze_panes.map(|(row, _)| row))
+            .unwrap_or(0)
+    }
+
+    /// Adds a continuation worksheet, which already has the header writes made so far.
+    ///
+    /// The header rows are fixed from the first continuation worksheet on, so that rows
+    /// keep their worksheet even if the panes are frozen again later.
+    pub fn push_continuation(&mut self, index: usize) {
+        self.header_rows = Some(self.header_rows());
+        self.continuations.push(Continuation {
+            index,
+            replayed: self.header_writes.len(),
+        });
+    }
+
+    /// Returns the worksheet of a logical row, `0` for the worksheet itself and `n` for
+    /// its `n`th continuation worksheet, and the row index in that worksheet.
+    pub fn locate(&self, row: RowNum) -> (usize, RowNum) {
+        if row < ROW_MAX {
+            return (0, row);
+        }
+        let header_rows = self.header_rows();
+        let rows_per_sheet = ROW_MAX - header_rows;
+        let index = row - ROW_MAX;
+        (
+            1 + (index / rows_per_sheet) as usize,
+            header_rows + index % rows_per_sheet,
+        )
+    }
+
+    /// Returns the logical rows written to the worksheet `part`, as numbered by `locate`,
+    /// and the row index of the first of them in that worksheet.
+    pub fn rows(&self, part: usize) -> (Range<u64>, RowNum) {
+        if part == 0 {
+            return (0..u64::from(ROW_MAX), 0);
+        }
+        let header_rows = self.header_rows();
+        let rows_per_sheet = u64::from(ROW_MAX - header_rows);
+        let first = u64::from(ROW_MAX) + (part as u64 - 1) * rows_per_sheet;
+        (first..first + rows_per_sheet, header_rows)
+    }
+
+    /// The name of the `n`th continuation worksheet, such as "Data (2)" for the first one.
+    /// The name of the worksheet is shortened if needed, to fit Excel's limit of 31 characters.
+    pub fn continuation_name(&self, n: usize) -> String {
+        let suffix = format!(" ({})", n + 1);
+        let name: String = self
+            .name
+            .chars()
+            .take(SHEET_NAME_MAX - suffix.chars().count())
+            .collect();
+        format!("{}{suffix}", name.trim_end())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locate_continues_rows_below_the_header_rows() {
+        let rollover = Rollover::new("Data".to_string(), Some(2));
+        assert_eq!(rollover.locate(0), (0, 0));
+        assert_eq!(rollover.locate(ROW_MAX - 1), (0, ROW_MAX - 1));
+        assert_eq!(rollover.locate(ROW_MAX), (1, 2));
+        assert_eq!(rollover.locate(2 * ROW_MAX - 3), (1, ROW_MAX - 1));
+        assert_eq!(rollover.locate(2 * ROW_MAX - 2), (2, 2));
+    }
+
+    #[test]
+    fn header_rows_default_to_the_frozen_rows() {
+        let mut rollover = Rollover::new("Data".to_string(), None);
+        assert_eq!(rollover.locate(ROW_MAX), (1, 0));
+        rollover.freeze_panes = Some((3, 1));
+        assert_eq!(rollover.header_rows(), 3);
+        assert_eq!(rollover.locate(ROW_MAX), (1, 3));
+    }
+
+    #[test]
+    fn header_rows_are_fixed_by_the_first_continuation() {
+        let mut rollover = Rollover::new("Data".to_string(), None);
+        rollover.freeze_panes = Some((1, 0));
+        rollover.push_continuation(1);
+        rollover.freeze_panes = Some((3, 0));
+        assert_eq!(rollover.header_rows(), 1);
+        assert_eq!(rollover.locate(ROW_MAX), (1, 1));
+    }
+
+    #[test]
+    fn range_formats_are_cut_at_the_last_header_row() {
+        let range = HeaderWrite::RangeFormat {
+            first: (4, 0),
+            last: (1, 2),
+            format: 0,
+        };
+        match range.clone().clip(2) {
+            Some(HeaderWrite::RangeFormat { first, last, .. }) => {
+                assert_eq!((first, last), ((1, 0), (1, 2)));
+            }
+            _ => panic!("expected a range format"),
+        }
+        assert!(range.clip(1).is_none());
+    }
+
+    #[test]
+    fn rows_match_locate() {
+        let rollover = Rollover::new("Data".to_string(), Some(2));
+        for part in 0..3 {
+            let (rows, first_row) = rollover.rows(part);
+            let first = rows.start as RowNum;
+            let last = (rows.end - 1) as RowNum;
+            assert_eq!(rollover.locate(first), (part, first_row));
+            assert_eq!(rollover.locate(last), (part, ROW_MAX - 1));
+        }
+    }
+
+    #[test]
+    fn continuation_names_fit_excel_limit() {
+        let rollover = Rollover::new("Data".to_string(), None);
+        assert_eq!(rollover.continuation_name(1), "Data (2)");
+        assert_eq!(rollover.continuation_name(10), "Data (11)");
+
+        let long = Rollover::new("Quarterly shipments by carrier".to_string(), None);
+        let name = long.continuation_name(1);
+        assert_eq!(name, "Quarterly shipments by carr (2)");
+        assert_eq!(name.chars().count(), SHEET_NAME_MAX);
+
+        // Trailing spaces left by shortening the name are trimmed.
+        let spaced = Rollover::new("Shipments by region and by day".to_string(), None);
+        assert_eq!(
+            spaced.continuation_name(1),
+            "Shipments by region and by (2)"
+        );
+    }
+}
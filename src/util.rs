@@ -1,13 +1,157 @@
+use pyo3::exceptions::PyTypeError;
 use pyo3::prelude::*;
+use pyo3::types::{PyFloat, PyString, PyTypeMethods};
 
-#[derive(FromPyObject)]
+use crate::datetime::{DateTimeKind, DateTimeValue, DateValue, TimeDeltaValue, TimeValue};
+use crate::error_value::ExcelErrorValue;
+use crate::format::{FormatId, FormatOption, FormatRegistry};
+use crate::number::{DecimalValue, IntValue};
+
+/// A value passed to `ExcelWorkbook.write` and the bulk write methods.
+#[derive(Clone)]
 pub enum ValueType {
-    #[pyo3(transparent, annotation = "str")]
     String(String),
+    Bool(bool),
+    Int(IntValue),
+    Missing(MissingValue),
+    Decimal(DecimalValue),
+    Float(f64),
+    DateTime(DateTimeValue),
+    Date(DateValue),
+    Time(TimeValue),
+    TimeDelta(TimeDeltaValue),
+    Error(ExcelErrorValue),
+    RichString(RichString),
+}
+
+impl<'py> FromPyObject<'py> for ValueType {
+    /// The types are checked one by one instead of with `derive(FromPyObject)`, which
+    /// builds an error message for every type that does not match and slows down every
+    /// value extracted after a few types.
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        if let Ok(value) = ob.downcast::<PyString>() {
+            return Ok(ValueType::String(value.to_str()?.to_owned()));
+        }
+        if let Ok(value) = ob.downcast::<PyFloat>() {
+            return Ok(ValueType::Float(value.value()));
+        }
+        // `bool` is a subclass of `int`, so it is extracted first.
+        if let Ok(value) = ob.extract::<bool>() {
+            return Ok(ValueType::Bool(value));
+        }
+        if let Ok(value) = ob.extract::<IntValue>() {
+            return Ok(ValueType::Int(value));
+        }
+        // `pandas.NaT` is a subclass of `datetime`, so it is extracted before dates.
+        if let Ok(value) = ob.extract::<MissingValue>() {
+            return Ok(ValueType::Missing(value));
+        }
+        if let Ok(value) = ob.extract::<DecimalValue>() {
+            return Ok(ValueType::Decimal(value));
+        }
+        // `datetime` is a subclass of `date`, so it is extracted first.
+        if let Ok(value) = ob.extract::<DateTimeValue>() {
+            return Ok(ValueType::DateTime(value));
+        }
+        if let Ok(value) = ob.extract::<DateValue>() {
+            return Ok(ValueType::Date(value));
+        }
+        if let Ok(value) = ob.extract::<TimeValue>() {
+            return Ok(ValueType::Time(value));
+        }
+        if let Ok(value) = ob.extract::<TimeDeltaValue>() {
+            return Ok(ValueType::TimeDelta(value));
+        }
+        if let Ok(value) = ob.extract::<ExcelErrorValue>() {
+            return Ok(ValueType::Error(value));
+        }
+        if let Ok(value) = ob.extract::<RichString>() {
+            return Ok(ValueType::RichString(value));
+        }
+        // Other numbers, such as `numpy.float32`, are converted with `__float__`.
+        if let Ok(value) = ob.extract::<f64>() {
+            return Ok(ValueType::Float(value));
+        }
+        Err(PyTypeError::new_err(format!(
+            "Cannot write a value of type '{}'. Expected str, bool, int, float, Decimal, datetime, date, time, timedelta, ExcelErrorValue, None, or a list of (text, format) tuples.",
+            ob.get_type().name()?
+        )))
+    }
+}
+
+impl ValueType {
+    /// Returns the kind of date or time value, or `None` for other values.
+    pub fn datetime_kind(&self) -> Option<DateTimeKind> {
+        match self {
+            ValueType::DateTime(_) => Some(DateTimeKind::DateTime),
+            ValueType::Date(_) => Some(DateTimeKind::Date),
+            ValueType::Time(_) => Some(DateTimeKind::Time),
+            ValueType::TimeDelta(_) => Some(DateTimeKind::TimeDelta),
+            _ => None,
+        }
+    }
+}
+
+/// A missing value of pandas, `pandas.NaT` or `pandas.NA`, which is written like `None`.
+#[derive(Clone)]
+pub struct MissingValue;
+
+impl<'py> FromPyObject<'py> for MissingValue {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        let ty = ob.get_type();
+        let is_missing = matches!(ty.name()?.to_str()?, "NaTType" | "NAType")
+            && ty.module()?.to_str()?.starts_with("pandas");
+        if !is_missing {
+            return Err(PyTypeError::new_err("expected pandas.NaT or pandas.NA"));
+        }
+        Ok(MissingValue)
+    }
+}
+
+/// The segments of a rich string, each written with its own font format.
+#[derive(Clone, FromPyObject)]
+#[pyo3(transparent)]
+pub struct RichString(Vec<(String, Option<FormatOption>)>);
+
+impl RichString {
+    /// Returns the id of the format of every segment, or `None` for unformatted segments.
+    pub fn format_ids(&self, formats: &mut FormatRegistry) -> PyResult<Vec<Option<FormatId>>> {
+        self.0
+            .iter()
+            .map(|(_, format_option)| {
+                format_option
+                    .as_ref()
+                    .map(|format_option| formats.resolve(format_option))
+                    .transpose()
+            })
+            .collect()
+    }
+
+    /// Returns the text of every segment.
+    pub fn texts(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(|(text, _)| text.as_str())
+    }
+}
+
+/// The cached result of a formula, shown by Excel until the formula is recalculated.
+#[derive(FromPyObject)]
+pub enum FormulaResult {
     #[pyo3(transparent, annotation = "bool")]
     Bool(bool),
-    #[pyo3(transparent, annotation = "int")]
-    Int(f64),
     #[pyo3(transparent, annotation = "float")]
-    Float(f64),
+    Number(f64),
+    #[pyo3(transparent, annotation = "str")]
+    String(String),
+}
+
+impl FormulaResult {
+    /// Returns the result as Excel stores it, e.g. `TRUE` for booleans.
+    pub fn to_result_string(&self) -> String {
+        match self {
+            FormulaResult::Bool(true) => "TRUE".to_string(),
+            FormulaResult::Bool(false) => "FALSE".to_string(),
+            FormulaResult::Number(value) => value.to_string(),
+            FormulaResult::String(value) => value.clone(),
+        }
+    }
 }
@@ -1,9 +1,25 @@
-use super::format::{self, ExcelFormat};
+use std::collections::HashSet;
+use std::ops::Range;
+
+use pyo3::types::PyDict;
+
+use super::array::ArrayValues;
+use super::dataframe::DataFrameValues;
+use super::datetime::{DateTimeFormats, TimezonePolicy};
+use super::error_value::{NonFinitePolicies, NonFinitePolicy};
+use super::format::{
+    BorderOption, ExcelFormat, FormatId, FormatIds, FormatOption, FormatOptions, FormatRegistry,
+};
+use super::layers::{FormatLayers, ROW_MAX};
+use super::number::{DecimalRounding, DecimalRoundingMode, IntOverflowPolicy};
 use pyo3::prelude::*;
-use rust_xlsxwriter::{ColNum, Format, RowNum, Workbook};
+use rust_xlsxwriter::{ColNum, Format, Formula, RowNum, Url, Workbook, Worksheet, XlsxError};
 
-use crate::util::ValueType;
-use crate::writer;
+use crate::errors;
+use crate::protection::ExcelProtectionOptions;
+use crate::rollover::{HeaderWrite, Rollover, SheetOverflowPolicy};
+use crate::util::{FormulaResult, RichString, ValueType};
+use crate::writer::{self, StringOverflow, StringOverflowPolicy, StringOverflowRecord};
 
 #[pyclass]
 /// The `ExcelWorkbook` struct represents an Excel workbook.
@@ -13,12 +29,59 @@ use crate::writer;
 pub struct ExcelWorkbook {
     workbook: Workbook,
     active_worksheet_index: usize,
+    string_overflow: StringOverflow,
+    formats: FormatRegistry,
+    layers: Vec<FormatLayers>,
+    hyperlinks: Vec<HashSet<(RowNum, ColNum)>>,
+    rollovers: Vec<Option<Rollover>>,
+    hyperlink_format: Option<FormatId>,
+    datetime_formats: DateTimeFormats,
+    tz_policy: TimezonePolicy,
+    int_overflow: IntOverflowPolicy,
+    decimal_rounding: DecimalRounding,
+    non_finite: NonFinitePolicies,
+    constant_memory: bool,
 }
 
 #[pymethods]
 impl ExcelWorkbook {
     #[new]
     /// Create a new workbook.
+    ///
+    /// ## Parameters
+    /// - `use_zip64`: Whether to use ZIP64 extensions for files larger than 4GB
+    /// - `string_overflow`: What to do with strings longer than Excel's limit of 32,767 characters.
+    ///   One of `"truncate"` (default), `"error"`, `"spill_to_next_cells"` or `"attach_as_comment"`
+    /// - `truncate_suffix`: The text to end truncated strings with, e.g. `"…"` _(optional)_
+    /// - `strict`: Whether an invalid border style or color passed to `draw_border` raises `ValueError`
+    ///   (default), or only emits a `UserWarning` and falls back to the default option. The options of
+    ///   an `ExcelFormat` always raise `ValueError` when the format is created or the option is assigned
+    /// - `date_format`: The number format of `date` values (default `"yyyy-mm-dd"`)
+    /// - `datetime_format`: The number format of `datetime` values (default `"yyyy-mm-dd hh:mm:ss"`)
+    /// - `time_format`: The number format of `time` values (default `"hh:mm:ss"`)
+    /// - `timedelta_format`: The number format of `timedelta` values (default `"[h]:mm:ss"`)
+    /// - `tz_policy`: What to do with timezone-aware `datetime` and `time` values, since Excel
+    ///   has no timezones. One of `"raise"` (default), `"utc"` to convert them to UTC,
+    ///   or `"drop"` to keep their local time
+    /// - `int_overflow`: What to do with integers beyond 2^53, which Excel cannot store exactly.
+    ///   One of `"text"` (default) to write them as strings, `"number"` to write the nearest number
+    ///   with a `UserWarning`, or `"error"`
+    /// - `decimal_places`: The number of decimal places to round `Decimal` values to _(optional)_
+    /// - `decimal_rounding`: The rounding mode of `Decimal` values, one of `"half_even"` (default),
+    ///   `"half_up"`, `"half_down"`, `"up"`, `"down"`, `"ceiling"`, `"floor"` or `"05up"`
+    /// - `nan_policy`: What to do with NaN numbers, which Excel cannot store. One of `"missing"`
+    ///   (default) to write them like `None`, as an empty cell or the `override_value` of the write,
+    ///   `"raise"`, `"blank"`, an Excel error value such as `"#N/A"` or `ExcelErrorValue.NA`,
+    ///   or `("text", string)` to write the string instead of the number. This applies to every
+    ///   write method, so the NaN of pandas and numpy float values are missing values by default
+    /// - `inf_policy`: What to do with infinite numbers, with the same options as `nan_policy`
+    ///   and `"raise"` as the default
+    /// - `constant_memory`: Whether worksheets are written to disk row by row instead of being kept
+    ///   in memory, for very large exports (default `False`). Rows must then be written in order:
+    ///   writing above the current row of a worksheet raises `RowOrderError`
+    /// - `tempdir`: The directory of the temporary files of `constant_memory` worksheets,
+    ///   instead of the system temporary directory _(optional)_
+    ///
     /// ## Examples
     /// The following example demonstrates creating a simple workbook, with one unused worksheet.
     /// ```
@@ -29,28 +92,222 @@ impl ExcelWorkbook {
     ///     workbook.add_worksheet()
     ///     workbook.save("example.xlsx")
     /// ```
-    #[pyo3(signature = (use_zip64=false))]
-    pub fn new(use_zip64: bool) -> ExcelWorkbook {
+    ///
+    /// The following example demonstrates exporting millions of rows in constant memory.
+    /// Formats of rows, ranges and borders still apply to the rows written afterwards.
+    /// ```
+    /// from pyaccelsx import ExcelWorkbook, ExcelFormat
+    ///
+    /// def main():
+    ///     workbook = ExcelWorkbook(constant_memory=True, tempdir="/mnt/scratch")
+    ///     workbook.add_worksheet("Shipments")
+    ///     workbook.set_column_width(0, 20)
+    ///     workbook.freeze_panes(1, 0)
+    ///
+    ///     workbook.write_row(0, 0, ["Reference", "Weight"], ExcelFormat(bold=True))
+    ///     for row in range(1, 3_000_001):
+    ///         workbook.write_row(row, 0, [f"SHP-{row}", row * 1.5])
+    ///
+    ///     workbook.save("example.xlsx")
+    /// ```
+    ///
+    /// ## Raises
+    /// - `ExcelIOError`: If `tempdir` is not a writable directory
+    /// - `ParameterError`: If `truncate_suffix` is longer than Excel's limit of 32,767 characters
+    #[pyo3(signature = (
+        use_zip64=false,
+        string_overflow=StringOverflowPolicy::Truncate,
+        truncate_suffix=None,
+        strict=true,
+        date_format="yyyy-mm-dd".to_string(),
+        datetime_format="yyyy-mm-dd hh:mm:ss".to_string(),
+        time_format="hh:mm:ss".to_string(),
+        timedelta_format="[h]:mm:ss".to_string(),
+        tz_policy=TimezonePolicy::Raise,
+        int_overflow=IntOverflowPolicy::Text,
+        decimal_places=None,
+        decimal_rounding=DecimalRoundingMode::HALF_EVEN,
+        nan_policy=NonFinitePolicy::Missing,
+        inf_policy=NonFinitePolicy::Raise,
+        constant_memory=false,
+        tempdir=None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        use_zip64: bool,
+        string_overflow: StringOverflowPolicy,
+        truncate_suffix: Option<String>,
+        strict: bool,
+        date_format: String,
+        datetime_format: String,
+        time_format: String,
+        timedelta_format: String,
+        tz_policy: TimezonePolicy,
+        int_overflow: IntOverflowPolicy,
+        decimal_places: Option<i32>,
+        decimal_rounding: DecimalRoundingMode,
+        nan_policy: NonFinitePolicy,
+        inf_policy: NonFinitePolicy,
+        constant_memory: bool,
+        tempdir: Option<String>,
+    ) -> PyResult<ExcelWorkbook> {
         let mut workbook = Workbook::new();
         if use_zip64 {
             workbook.use_zip_large_file(true);
         }
-        ExcelWorkbook {
+        if let Some(tempdir) = tempdir {
+            workbook
+                .set_tempdir(tempdir)
+                .map_err(|error| errors::to_py_err(error, None, None))?;
+        }
+        Ok(ExcelWorkbook {
             workbook,
             active_worksheet_index: 0,
+            string_overflow: StringOverflow::new(string_overflow, truncate_suffix)?,
+            formats: FormatRegistry::new(strict),
+            layers: Vec::new(),
+            hyperlinks: Vec::new(),
+            rollovers: Vec::new(),
+            hyperlink_format: None,
+            datetime_formats: DateTimeFormats::new(
+                date_format,
+                datetime_format,
+                time_format,
+                timedelta_format,
+            ),
+            tz_policy,
+            int_overflow,
+            decimal_rounding: DecimalRounding {
+                places: decimal_places,
+                mode: decimal_rounding,
+            },
+            non_finite: NonFinitePolicies {
+                nan: nan_policy,
+                inf: inf_policy,
+            },
+            constant_memory,
+        })
+    }
+
+    /// The strings that exceeded Excel's cell limit, in the order they were written.
+    ///
+    /// Each record has the `sheet`, `cell`, `row`, `column`, `original_length`,
+    /// `written_length` and `policy` of the overflowing string.
+    /// Lengths are counted in UTF-16 code units, the same way Excel counts them.
+    ///
+    /// ## Examples
+    /// The following example demonstrates reading the report after saving a workbook.
+    /// ```
+    /// from pyaccelsx import ExcelWorkbook
+    ///
+    /// def main():
+    ///     workbook = ExcelWorkbook(truncate_suffix="…")
+    ///     workbook.add_worksheet()
+    ///     workbook.write(0, 0, "a" * 40000)
+    ///     workbook.save("example.xlsx")
+    ///
+    ///     for record in workbook.string_overflow_report:
+    ///         print(record.cell, record.original_length, record.written_length)
+    /// ```
+    #[getter]
+    pub fn string_overflow_report(&self) -> Vec<StringOverflowRecord> {
+        self.string_overflow.report.clone()
+    }
+
+    /// The number of distinct formats used in the workbook so far.
+    ///
+    /// Every distinct `ExcelFormat` passed to a write method is built once and reused
+    /// afterwards, so a large count usually means formats are being created per cell.
+    #[getter]
+    pub fn format_count(&self) -> usize {
+        self.formats.len()
+    }
+
+    #[pyo3(signature = (name, format_option=None, base=None, **options))]
+    /// Registers a named style, which can then be passed as `format_option` to every
+    /// write method instead of an `ExcelFormat`. Registering an existing name replaces the style.
+    ///
+    /// A style can inherit from another registered style with `base`. The options of
+    /// `format_option` are applied on top of the base style, and the keyword options
+    /// (the same as `ExcelFormat`) are applied last.
+    ///
+    /// ## Parameters
+    /// - `name`: The name of the style
+    /// - `format_option`: The format of the style _(optional)_
+    /// - `base`: The name of a registered style to inherit from _(optional)_
+    /// - `**options`: `ExcelFormat` options to set on the style _(optional)_
+    ///
+    /// ## Examples
+    /// The following example demonstrates registering and using named styles.
+    /// ```
+    /// from pyaccelsx import ExcelWorkbook, ExcelFormat
+    ///
+    /// def main():
+    ///     workbook = ExcelWorkbook()
+    ///     workbook.add_worksheet()
+    ///
+    ///     workbook.register_style("header", ExcelFormat(bold=True, border=True))
+    ///     workbook.register_style("header_red", base="header", font_color="FF0000")
+    ///
+    ///     workbook.write(0, 0, "Name", format_option="header")
+    ///     workbook.write(0, 1, "Overdue", format_option="header_red")
+    ///
+    ///     workbook.save("example.xlsx")
+    /// ```
+    pub fn register_style(
+        &mut self,
+        py: Python<'_>,
+        name: String,
+        format_option: Option<ExcelFormat>,
+        base: Option<String>,
+        options: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<()> {
+        let mut style = match base {
+            Some(base) => self.formats.style(&base)?.clone(),
+            None => ExcelFormat::default(),
+        };
+        if let Some(format_option) = format_option {
+            style = style.overlay(&format_option);
+        }
+        if let Some(options) = options {
+            // Build the options through the `ExcelFormat` constructor, so they are type checked.
+            let options: ExcelFormat = py
+                .get_type::<ExcelFormat>()
+                .call((), Some(options))?
+                .extract()?;
+            style = style.overlay(&options);
         }
+        self.formats.register_style(name, style);
+        Ok(())
     }
 
     /// Add a new worksheet to the workbook and update the active worksheet index.
-    #[pyo3(signature = (name=None))]
+    #[pyo3(signature = (name=None, overflow=SheetOverflowPolicy::Error, header_rows=None))]
     /// Adds a new worksheet to the workbook with the given sheet name.
     /// If no name is given, the standard names (`Sheet1`, `Sheet2`, etc.) will be used.
     ///
     /// Pyaccelsx used `active_worksheet_index` to keep track of the active worksheet.
     /// Adding a new worksheet into the workbook will automatically increment `active_worksheet_index`.
     ///
+    /// With `overflow="new_sheet"`, rows past Excel's limit of 1,048,576 rows continue on new
+    /// worksheets named like "Data (2)", added at the end of the workbook. Rows keep being written
+    /// to this worksheet with growing row indexes, e.g. row `1_048_576` is the first row below the
+    /// header of "Data (2)". Every new worksheet repeats the header rows, the column widths, the frozen
+    /// panes and the column formats of this worksheet. Merges, array formulas, range formats and borders
+    /// are written to the worksheet of their rows, and must fit in a single worksheet.
+    ///
+    /// Everything written to the header rows is repeated, including blank cells, links, formulas,
+    /// merges, and row, range and border formats. Merges and array formulas starting in the header rows
+    /// must end in them. Header rows written after the first new worksheet was added are also written to
+    /// the new worksheets, which a `constant_memory` workbook only allows before their data rows. The
+    /// number of header rows is fixed once the first new worksheet is added.
+    ///
     /// ## Parameters
     /// - `name`: The name of the new worksheet _(optional)_
+    /// - `overflow`: What to do with rows past the last row of the worksheet. One of `"error"` (default)
+    ///   to raise `RowColumnLimitError`, or `"new_sheet"` to continue on a new worksheet
+    /// - `header_rows`: The number of rows repeated at the top of each new worksheet. Defaults to
+    ///   the rows above the frozen panes, which must then be frozen before the header is written _(optional)_
     ///
     /// ## Examples
     /// The following example demonstrates adding worksheets to a workbook.
@@ -69,16 +326,44 @@ impl ExcelWorkbook {
     ///     
     ///     workbook.save("example.xlsx")
     /// ```
-    pub fn add_worksheet(&mut self, name: Option<String>) -> PyResult<()> {
-        if name.is_none() {
-            self.workbook.add_worksheet();
-        } else {
-            self.workbook
-                .add_worksheet()
-                .set_name(name.unwrap())
-                .unwrap();
+    ///
+    /// The following example demonstrates exporting more rows than a single worksheet can hold.
+    /// ```
+    /// from pyaccelsx import ExcelWorkbook, ExcelFormat
+    ///
+    /// def main():
+    ///     workbook = ExcelWorkbook(constant_memory=True)
+    ///     workbook.add_worksheet("Data", overflow="new_sheet")
+    ///     workbook.freeze_panes(1, 0)
+    ///     workbook.set_column_width(0, 20)
+    ///
+    ///     workbook.write_row(0, 0, ["Reference", "Weight"], ExcelFormat(bold=True))
+    ///     for row in range(1, 3_000_001):
+    ///         workbook.write_row(row, 0, [f"SHP-{row}", row * 1.5])  // Data, Data (2) and Data (3)
+    ///
+    ///     workbook.save("example.xlsx")
+    /// ```
+    ///
+    /// ## Raises
+    /// - `SheetNameError`: If the name is invalid or already used by another worksheet
+    /// - `ParameterError`: If `header_rows` is not below Excel's row limit
+    pub fn add_worksheet(
+        &mut self,
+        name: Option<String>,
+        overflow: SheetOverflowPolicy,
+        header_rows: Option<RowNum>,
+    ) -> PyResult<()> {
+        if header_rows.is_some_and(|header_rows| header_rows >= ROW_MAX) {
+            let error = XlsxError::ParameterError(format!(
+                "header_rows must be below Excel's limit of {ROW_MAX} rows."
+            ));
+            return Err(errors::to_py_err(error, name.as_deref(), None));
+        }
+        self.push_worksheet(name)?;
+        if overflow == SheetOverflowPolicy::NewSheet {
+            let name = self.workbook.worksheets()[self.active_worksheet_index].name();
+            self.rollovers[self.active_worksheet_index] = Some(Rollover::new(name, header_rows));
         }
-        self.active_worksheet_index = self.workbook.worksheets().len() - 1;
         Ok(())
     }
 
@@ -119,8 +404,17 @@ impl ExcelWorkbook {
     ///     workbook.add_worksheet()
     ///     workbook.save("example.xlsx")
     /// ```
+    ///
+    /// ## Raises
+    /// - `ExcelIOError`: If the file cannot be written
     pub fn save(&mut self, path: String) -> PyResult<()> {
-        self.workbook.save(path).unwrap();
+        for (index, layers) in self.layers.iter_mut().enumerate() {
+            let worksheet = worksheet_at(&mut self.workbook, index)?;
+            layers.finish(worksheet, &mut self.formats)?;
+        }
+        self.workbook
+            .save(path)
+            .map_err(|error| errors::to_py_err(error, None, None))?;
         Ok(())
     }
 
@@ -130,16 +424,23 @@ impl ExcelWorkbook {
     /// ## Parameters
     /// - `row`: The row number of the cell
     /// - `column`: The column number of the cell
-    /// - `value`: The value to write
+    /// - `value`: The value to write. Dates, datetimes, times and timedeltas are written as Excel
+    ///   dates, with the number format of the workbook unless `format_option` sets one.
+    ///   A list of `(text, format)` tuples is written as a rich string, see `write_rich_string`
     /// - `override_true_value`: The value to write if the value is `True`
     /// - `override_false_value`: The value to write if the value is `False`
-    /// - `override_value`: The value to write if the value is `None`
-    /// - `format_option`: The format to apply to the cell
+    /// - `override_value`: The value to write if the value is `None`, `pandas.NaT`, `pandas.NA`, or NaN with the default `nan_policy`
+    /// - `format_option`: The format to apply to the cell, or the name of a registered style
+    ///
+    /// Scalars of numpy and pandas, such as `numpy.int64`, `numpy.float32`, `numpy.bool_`
+    /// and `pandas.Timestamp`, are written like the matching Python values.
     ///
     /// ## Examples
     /// The following example demonstrates writing a value to a cell in a workbook.
     /// ```
-    /// from pyaccelsx import ExcelWorkbook, ExcelFormat
+    /// import datetime
+    ///
+    /// from pyaccelsx import ExcelWorkbook, ExcelErrorValue, ExcelFormat
     ///
     /// def main():
     ///     workbook = ExcelWorkbook()
@@ -155,8 +456,13 @@ impl ExcelWorkbook {
     ///     workbook.write(3, 0, 3.14, format_option=ExcelFormat(num_format="#,##0.00"))
     ///     // Write None
     ///     workbook.write(4, 0, None, override_value="Empty")
+    ///     // Write a date, formatted with the workbook `date_format`
+    ///     workbook.write(5, 0, datetime.date(2024, 3, 1))
+    ///     // Write an Excel error value
+    ///     workbook.write(6, 0, ExcelErrorValue.NA)
     ///     
     ///     workbook.save("example.xlsx")
+    #[allow(clippy::too_many_arguments)]
     pub fn write(
         &mut self,
         row: RowNum,
@@ -165,282 +471,2254 @@ impl ExcelWorkbook {
         override_true_value: Option<String>,
         override_false_value: Option<String>,
         override_value: Option<String>,
-        format_option: Option<ExcelFormat>,
+        format_option: Option<FormatOption>,
     ) -> PyResult<()> {
-        let worksheet = self
-            .workbook
-            .worksheet_from_index(self.active_worksheet_index)
-            .unwrap();
-
-        if let Some(value) = value {
-            match value {
-                ValueType::String(value) => {
-                    writer::write_string(worksheet, row, column, value, format_option)
-                }
-                ValueType::Bool(value) => writer::write_boolean(
-                    worksheet,
-                    row,
-                    column,
-                    value,
-                    override_true_value,
-                    override_false_value,
-                    format_option,
-                ),
-                ValueType::Int(value) => {
-                    writer::write_number(worksheet, row, column, value, format_option)
-                }
-                ValueType::Float(value) => {
-                    writer::write_number(worksheet, row, column, value, format_option)
-                }
-            }
-            .unwrap();
-        } else {
-            writer::write_null(worksheet, row, column, override_value, format_option).unwrap();
-        }
-
-        Ok(())
+        let format = match &format_option {
+            Some(format_option) => Some(self.formats.resolve(format_option)?),
+            None => None,
+        };
+        let overrides = ValueOverrides {
+            true_value: override_true_value,
+            false_value: override_false_value,
+            missing_value: override_value,
+        };
+        self.write_value(row, column, value, &overrides, format)
     }
 
-    #[pyo3(signature = (row, column, format_option=None))]
-    /// Worksheet handler for writing a "blank" cell.
-    /// This function will only perform write if `format_option` is specified.
-    /// If there is no format option specified, the corresponding cell will be an "empty" cell.
+    #[pyo3(signature = (row, start_column, values, formats=None, override_true_value=None, override_false_value=None, override_value=None))]
+    /// Worksheet handler for writing values to consecutive cells of a row, in a single call.
     ///
-    /// See this [documentation](https://docs.rs/rust_xlsxwriter/0.75.0/rust_xlsxwriter/worksheet/struct.Worksheet.html#method.write_blank) for difference between "blank cell" and "empty cell".
+    /// The values are written the same way as with `write`, but without crossing between
+    /// Python and Rust for every cell.
     ///
     /// ## Parameters
-    /// - `row`: The row index of the cell
-    /// - `column`: The column index of the cell
-    /// - `format_option`: The format of the cell _(optional)_
+    /// - `row`: The row index of the cells
+    /// - `start_column`: The column index of the first value
+    /// - `values`: Any iterable of values, such as a list, tuple or generator
+    /// - `formats`: One format for every cell, or a sequence with the format of each cell,
+    ///   where `None` leaves a cell unformatted _(optional)_
+    /// - `override_true_value`: The value to write if a value is `True` _(optional)_
+    /// - `override_false_value`: The value to write if a value is `False` _(optional)_
+    /// - `override_value`: The value to write if a value is `None`, `pandas.NaT`, `pandas.NA`, or NaN with the default `nan_policy` _(optional)_
     ///
     /// ## Examples
-    /// The following example demonstrates writing a "blank" cell to a worksheet.
+    /// The following example demonstrates writing a header row.
     /// ```
     /// from pyaccelsx import ExcelWorkbook, ExcelFormat
     ///
     /// def main():
     ///     workbook = ExcelWorkbook()
     ///     workbook.add_worksheet()
-    ///     
-    ///     format_option = ExcelFormat(border=True)
-    ///     workbook.write_blank(0, 0, format_option)
-    ///     // This will not perform any write
-    ///     workbook.write_blank(0, 1)
+    ///
+    ///     workbook.write_row(0, 0, ["Shipment", "Carrier", "Amount"], ExcelFormat(bold=True))
+    ///     workbook.write_row(1, 0, ["S-001", None, 1234.5], override_value="-")
     ///
     ///     workbook.save("example.xlsx")
     /// ```
     ///
-    pub fn write_blank(
+    /// ## Raises
+    /// - `RowColumnLimitError`: If a value is outside of the worksheet limits
+    /// - `RowOrderError`: If a row is above the current row of a `constant_memory` worksheet
+    #[allow(clippy::too_many_arguments)]
+    pub fn write_row(
         &mut self,
         row: RowNum,
+        start_column: ColNum,
+        values: &Bound<'_, PyAny>,
+        formats: Option<FormatOptions>,
+        override_true_value: Option<String>,
+        override_false_value: Option<String>,
+        override_value: Option<String>,
+    ) -> PyResult<()> {
+        let formats = self.formats.resolve_all(formats.as_ref())?;
+        let overrides = ValueOverrides {
+            true_value: override_true_value,
+            false_value: override_false_value,
+            missing_value: override_value,
+        };
+        self.write_values((row, start_column), values, &formats, &overrides, false)
+    }
+
+    #[pyo3(signature = (start_row, column, values, formats=None, override_true_value=None, override_false_value=None, override_value=None))]
+    /// Worksheet handler for writing values to consecutive cells of a column, in a single call.
+    ///
+    /// ## Parameters
+    /// - `start_row`: The row index of the first value
+    /// - `column`: The column index of the cells
+    /// - `values`: Any iterable of values, such as a list, tuple or generator
+    /// - `formats`: One format for every cell, or a sequence with the format of each cell,
+    ///   where `None` leaves a cell unformatted _(optional)_
+    /// - `override_true_value`: The value to write if a value is `True` _(optional)_
+    /// - `override_false_value`: The value to write if a value is `False` _(optional)_
+    /// - `override_value`: The value to write if a value is `None`, `pandas.NaT`, `pandas.NA`, or NaN with the default `nan_policy` _(optional)_
+    ///
+    /// ## Examples
+    /// The following example demonstrates writing a column of amounts.
+    /// ```
+    /// from pyaccelsx import ExcelWorkbook, ExcelFormat
+    ///
+    /// def main():
+    ///     workbook = ExcelWorkbook()
+    ///     workbook.add_worksheet()
+    ///
+    ///     amounts = [1234.5, 99.0, 10.25]
+    ///     workbook.write_column(1, 0, amounts, ExcelFormat(num_format="#,##0.00"))
+    ///
+    ///     workbook.save("example.xlsx")
+    /// ```
+    ///
+    /// ## Raises
+    /// - `RowColumnLimitError`: If a value is outside of the worksheet limits
+    /// - `RowOrderError`: If a row is above the current row of a `constant_memory` worksheet
+    #[allow(clippy::too_many_arguments)]
+    pub fn write_column(
+        &mut self,
+        start_row: RowNum,
         column: ColNum,
-        format_option: Option<ExcelFormat>,
+        values: &Bound<'_, PyAny>,
+        formats: Option<FormatOptions>,
+        override_true_value: Option<String>,
+        override_false_value: Option<String>,
+        override_value: Option<String>,
     ) -> PyResult<()> {
-        if let Some(format_option) = format_option {
-            let worksheet = self
-                .workbook
-                .worksheet_from_index(self.active_worksheet_index)
-                .unwrap();
-            let format = format::create_format(format_option);
-            worksheet.write_blank(row, column, &format).unwrap();
-        }
-        Ok(())
+        let formats = self.formats.resolve_all(formats.as_ref())?;
+        let overrides = ValueOverrides {
+            true_value: override_true_value,
+            false_value: override_false_value,
+            missing_value: override_value,
+        };
+        self.write_values((start_row, column), values, &formats, &overrides, true)
     }
 
-    #[pyo3(signature = (start_row, start_column, end_row, end_column, format_option=None))]
-    /// Worksheet handler for merging a range of cells. This will not do any
-    /// writing to the cell values. To write values, use `write_and_merge_range`.
+    #[pyo3(signature = (start_row, start_column, rows, column_formats=None, override_true_value=None, override_false_value=None, override_value=None))]
+    /// Worksheet handler for writing a table of values, one row after another, in a single call.
     ///
     /// ## Parameters
-    /// - `start_row`: The start row index of the range
-    /// - `start_column`: The start column index of the range
-    /// - `end_row`: The end row index of the range
-    /// - `end_column`: The end column index of the range
-    /// - `format_option`: The format of the cell _(optional)_
+    /// - `start_row`: The row index of the first row
+    /// - `start_column`: The column index of the first value of every row
+    /// - `rows`: Any iterable of rows, where every row is an iterable of values
+    /// - `column_formats`: One format for every cell, or a sequence with the format of each column,
+    ///   where `None` leaves a column unformatted _(optional)_
+    /// - `override_true_value`: The value to write if a value is `True` _(optional)_
+    /// - `override_false_value`: The value to write if a value is `False` _(optional)_
+    /// - `override_value`: The value to write if a value is `None`, `pandas.NaT`, `pandas.NA`, or NaN with the default `nan_policy` _(optional)_
     ///
     /// ## Examples
-    /// The following example demonstrates merging cells in a worksheet.
+    /// The following example demonstrates writing the rows of a query result.
     /// ```
     /// from pyaccelsx import ExcelWorkbook, ExcelFormat
     ///
     /// def main():
     ///     workbook = ExcelWorkbook()
     ///     workbook.add_worksheet()
-    ///     
-    ///     format_option = ExcelFormat(align="center", border=True)
-    ///     workbook.merge_range(0, 0, 0, 2, format_option)
+    ///
+    ///     rows = [("S-001", "Maersk", 1234.5), ("S-002", None, 99.0)]
+    ///     amount = ExcelFormat(num_format="#,##0.00")
+    ///     workbook.write_rows(0, 0, rows, column_formats=[None, None, amount])
     ///
     ///     workbook.save("example.xlsx")
     /// ```
-    pub fn merge_range(
+    ///
+    /// ## Raises
+    /// - `RowColumnLimitError`: If a value is outside of the worksheet limits
+    /// - `RowOrderError`: If a row is above the current row of a `constant_memory` worksheet
+    #[allow(clippy::too_many_arguments)]
+    pub fn write_rows(
         &mut self,
         start_row: RowNum,
         start_column: ColNum,
-        end_row: RowNum,
-        end_column: ColNum,
-        format_option: Option<ExcelFormat>,
+        rows: &Bound<'_, PyAny>,
+        column_formats: Option<FormatOptions>,
+        override_true_value: Option<String>,
+        override_false_value: Option<String>,
+        override_value: Option<String>,
     ) -> PyResult<()> {
-        let worksheet = self
-            .workbook
-            .worksheet_from_index(self.active_worksheet_index)
-            .unwrap();
-        if format_option.is_none() {
-            worksheet
-                .merge_range(
-                    start_row,
-                    start_column,
-                    end_row,
-                    end_column,
-                    "",
-                    &Format::new(),
-                )
-                .unwrap();
-        } else {
-            let format = format::create_format(format_option.unwrap());
-            worksheet
-                .merge_range(start_row, start_column, end_row, end_column, "", &format)
-                .unwrap();
+        let formats = self.formats.resolve_all(column_formats.as_ref())?;
+        let overrides = ValueOverrides {
+            true_value: override_true_value,
+            false_value: override_false_value,
+            missing_value: override_value,
+        };
+        for (index, values) in rows.try_iter()?.enumerate() {
+            let row = row_offset(start_row, index);
+            self.write_values((row, start_column), &values?, &formats, &overrides, false)?;
         }
         Ok(())
     }
 
-    #[pyo3(signature = (start_row, start_column, end_row, end_column, value=None, override_true_value=None, override_false_value=None, override_value=None, format_option=None))]
-    /// Worksheet handler for merging a range of cells and writing string value into the merged cells.
+    #[pyo3(signature = (start_row, start_column, array, format_option=None, override_value=None))]
+    /// Worksheet handler for writing a 1D or 2D numpy array of numbers, booleans or `datetime64` values.
+    ///
+    /// The array is read in place, without converting its values to Python objects.
+    /// A 1D array is written down a column. NaN and infinite numbers follow the `nan_policy` and
+    /// `inf_policy` of the workbook, like with `write`, so NaN values are missing values by default.
+    /// `NaT` values are missing values, written like `None` with `write`.
     ///
     /// ## Parameters
-    /// - `start_row`: The start row index of the range
-    /// - `start_column`: The start column index of the range
-    /// - `end_row`: The end row index of the range
-    /// - `end_column`: The end column index of the range
-    /// - `value`: The string value to write
-    /// - `override_true_value`: The string value to write if the cell value is `True` _(optional)_
-    /// - `override_false_value`: The string value to write if the cell value is `False` _(optional)_
-    /// - `override_value`: The string value to write if the cell value is not `True` or `False` _(optional)_
-    /// - `format_option`: The format of the cell _(optional)_
+    /// - `start_row`: The row index of the first row of the array
+    /// - `start_column`: The column index of the first column of the array
+    /// - `array`: A numpy array with a float, integer, bool or `datetime64` dtype
+    /// - `format_option`: The format of every cell, or the name of a registered style _(optional)_
+    /// - `override_value`: The value to write for `NaT` values, and NaN values with the default `nan_policy` _(optional)_
     ///
     /// ## Examples
-    /// The following example demonstrates merging cells and writing string value into the merged cells in a worksheet.
+    /// The following example demonstrates writing a matrix of amounts.
     /// ```
+    /// import numpy as np
+    ///
     /// from pyaccelsx import ExcelWorkbook, ExcelFormat
     ///
     /// def main():
     ///     workbook = ExcelWorkbook()
     ///     workbook.add_worksheet()
-    ///     
-    ///     format_option = ExcelFormat(align="center", border=True)
-    ///     workbook.write_and_merge_range(0, 0, 0, 2, "Hello World!", format_option)
+    ///
+    ///     amounts = np.array([[1234.5, np.nan], [99.0, 10.25]])
+    ///     workbook.write_array(1, 1, amounts, ExcelFormat(num_format="#,##0.00"), override_value="-")
     ///
     ///     workbook.save("example.xlsx")
     /// ```
-    pub fn write_and_merge_range(
+    ///
+    /// ## Raises
+    /// - `TypeError`: If the array has an unsupported dtype or more than 2 dimensions
+    /// - `ParameterError`: If a `datetime64` value is out of the range of Excel dates
+    /// - `RowColumnLimitError`: If the array does not fit in the worksheet
+    /// - `RowOrderError`: If a row is above the current row of a `constant_memory` worksheet
+    pub fn write_array(
         &mut self,
         start_row: RowNum,
         start_column: ColNum,
-        end_row: RowNum,
-        end_column: ColNum,
-        value: Option<ValueType>,
-        override_true_value: Option<String>,
-        override_false_value: Option<String>,
+        array: ArrayValues,
+        format_option: Option<FormatOption>,
         override_value: Option<String>,
-        format_option: Option<ExcelFormat>,
     ) -> PyResult<()> {
-        if let Some(value) = value {
-            // Prevent using moved value
-            let cloned_format_option = format_option.clone();
-            self.merge_range(start_row, start_column, end_row, end_column, format_option)
-                .unwrap();
-            if cloned_format_option.is_none() {
-                self.write(
-                    start_row,
-                    start_column,
-                    Some(value),
-                    override_true_value,
-                    override_false_value,
-                    override_value,
-                    cloned_format_option,
-                )
-                .unwrap();
-            } else {
-                self.write(
-                    start_row,
-                    start_column,
-                    Some(value),
-                    override_true_value,
-                    override_false_value,
-                    override_value,
-                    cloned_format_option,
-                )
-                .unwrap();
+        let format = match &format_option {
+            Some(format_option) => Some(self.formats.resolve(format_option)?),
+            None => None,
+        };
+        let overrides = ValueOverrides {
+            true_value: None,
+            false_value: None,
+            missing_value: override_value,
+        };
+        for row in 0..array.rows() {
+            let array_row = row;
+            let row = row_offset(start_row, array_row);
+            let segment = self.segment(row)?;
+            let mut writer = self.sheet_writer(&segment)?;
+            for column in 0..array.columns() {
+                writer.write(
+                    segment.row(row),
+                    column_offset(start_column, column),
+                    Some(array.value(array_row, column)?),
+                    &overrides,
+                    format,
+                )?;
             }
-        } else {
-            self.merge_range(start_row, start_column, end_row, end_column, format_option)
-                .unwrap();
         }
-        Ok(())
+        self.repeat_header_writes()
     }
 
-    /// Worksheet handler for setting column width.
+    #[pyo3(signature = (dataframe, start_row=0, start_column=0, header=true, index=false, column_formats=None))]
+    /// Worksheet handler for writing a dataframe, with its column names as a header row.
+    ///
+    /// The dataframe is read through the Arrow PyCapsule interface (`__arrow_c_stream__`),
+    /// directly from its column buffers, without converting its values to Python objects.
+    /// This accepts pyarrow Tables, polars DataFrames and pandas DataFrames (pandas 2.2 or later,
+    /// with pyarrow installed).
+    ///
+    /// Strings, numbers, booleans, dates, times, timestamps and durations are written like
+    /// the equivalent Python values, and categorical columns are written as their values.
+    /// Null values are left empty. Timezone-aware timestamps follow the `tz_policy` of the workbook.
+    /// Decimal columns, such as `pyarrow.decimal128` or polars `Decimal` columns, are not supported
+    /// yet; cast them to `float64` or strings before writing the dataframe.
     ///
     /// ## Parameters
-    /// - `column`: The column index of the cell
-    /// - `width`: The width of the column
+    /// - `dataframe`: A dataframe, or any table with an `__arrow_c_stream__` method
+    /// - `start_row`: The row index of the header, or of the first row if `header` is `False` (default `0`)
+    /// - `start_column`: The column index of the first column (default `0`)
+    /// - `header`: Whether to write the column names as the first row (default `True`)
+    /// - `index`: Whether to write the index of a pandas DataFrame as its first columns (default `False`).
+    ///   Other dataframes have no index. A pandas DataFrame is copied by `reset_index` before it is
+    ///   exported, so it briefly takes twice its memory
+    /// - `column_formats`: The format of every value cell, or a list with the format of each column,
+    ///   where `None` leaves the column unformatted _(optional)_
     ///
     /// ## Examples
-    /// The following example demonstrates setting column width in a worksheet.
+    /// The following example demonstrates writing a polars DataFrame below a title.
     /// ```
-    /// from pyaccelsx import ExcelWorkbook
+    /// import polars as pl
+    ///
+    /// from pyaccelsx import ExcelWorkbook, ExcelFormat
     ///
     /// def main():
     ///     workbook = ExcelWorkbook()
     ///     workbook.add_worksheet()
     ///
-    ///     workbook.write(0, 0, "Hello World!")
-    ///     workbook.set_column_width(0, 20)
+    ///     shipments = pl.DataFrame({"reference": ["SHP-1", "SHP-2"], "weight": [1250.5, 980.0]})
+    ///     workbook.write(0, 0, "Shipments")
+    ///     workbook.write_dataframe(
+    ///         shipments,
+    ///         start_row=2,
+    ///         column_formats=[None, ExcelFormat(num_format="#,##0.00")],
+    ///     )
     ///
     ///     workbook.save("example.xlsx")
     /// ```
-    pub fn set_column_width(&mut self, column: ColNum, width: f64) -> PyResult<()> {
-        let worksheet = self
-            .workbook
-            .worksheet_from_index(self.active_worksheet_index)
-            .unwrap();
-        worksheet.set_column_width(column, width).unwrap();
-        Ok(())
+    ///
+    /// ## Raises
+    /// - `TypeError`: If the dataframe does not support the Arrow PyCapsule interface,
+    ///   or has a column of an unsupported type, such as a list, struct or decimal column
+    /// - `ValueError`: If the dataframe fails to export its schema or a record batch
+    /// - `RowColumnLimitError`: If the dataframe does not fit in the worksheet
+    /// - `RowOrderError`: If a row is above the current row of a `constant_memory` worksheet
+    pub fn write_dataframe(
+        &mut self,
+        dataframe: &Bound<'_, PyAny>,
+        start_row: RowNum,
+        start_column: ColNum,
+        header: bool,
+        index: bool,
+        column_formats: Option<FormatOptions>,
+    ) -> PyResult<()> {
+        let formats = self.formats.resolve_all(column_formats.as_ref())?;
+        let overrides = ValueOverrides {
+            true_value: None,
+            false_value: None,
+            missing_value: None,
+        };
+        let dataframe = DataFrameValues::new(dataframe, index)?;
+        let mut row = start_row;
+        if header {
+            let segment = self.segment(row)?;
+            let mut writer = self.sheet_writer(&segment)?;
+            for (index, name) in dataframe.column_names().into_iter().enumerate() {
+                let column = column_offset(start_column, index);
+                let value = Some(ValueType::String(name));
+                writer.write(segment.row(row), column, value, &overrides, None)?;
+            }
+            row = row_offset(row, 1);
+        }
+        dataframe.for_each_row(self.tz_policy, |values| {
+            let segment = self.segment(row)?;
+            let mut writer = self.sheet_writer(&segment)?;
+            for (index, value) in values.into_iter().enumerate() {
+                let column = column_offset(start_column, index);
+                let format = formats.get(index);
+                writer.write(segment.row(row), column, Some(value), &overrides, format)?;
+            }
+            row = row_offset(row, 1);
+            Ok(())
+        })?;
+        self.repeat_header_writes()
     }
 
-    /// Worksheet handler for freezing panes.
+    #[pyo3(signature = (row, column, segments, format_option=None))]
+    /// Worksheet handler for writing a string with multiple formats to a cell.
+    ///
+    /// Only the font options of the segment formats, such as `bold`, `italic` and `font_color`,
+    /// are applied to the text. The cell options, such as borders and alignment, come from `format_option`.
+    ///
+    /// A rich string can also be written with `write` and `write_and_merge_range`,
+    /// by passing the segments as the value.
     ///
     /// ## Parameters
-    /// - `row`: The row index of the cell which will be frozen
-    /// - `column`: The column index of the cell which will be frozen
+    /// - `row`: The row index of the cell
+    /// - `column`: The column index of the cell
+    /// - `segments`: A list of `(text, format)` tuples, where `format` is an `ExcelFormat`,
+    ///   the name of a registered style, or `None` for unformatted text
+    /// - `format_option`: The format of the cell, or the name of a registered style _(optional)_
     ///
     /// ## Examples
-    /// The following example demonstrates freezing panes in a worksheet.
+    /// The following example demonstrates writing a status with a bold red part.
     /// ```
-    /// from pyaccelsx import ExcelWorkbook
+    /// from pyaccelsx import ExcelWorkbook, ExcelFormat
     ///
     /// def main():
     ///     workbook = ExcelWorkbook()
     ///     workbook.add_worksheet()
     ///
-    ///     workbook.write(0, 0, "Hello World!")
-    ///     // This freezes the first row and first column
-    ///     workbook.freeze_panes(0, 0)
+    ///     late = ExcelFormat(bold=True, font_color="FF0000")
+    ///     workbook.write_rich_string(0, 0, [("Status: ", None), ("LATE", late), (" (3 days)", None)])
     ///
     ///     workbook.save("example.xlsx")
     /// ```
-    pub fn freeze_panes(&mut self, row: RowNum, column: ColNum) -> PyResult<()> {
-        let worksheet = self
-            .workbook
-            .worksheet_from_index(self.active_worksheet_index)
-            .unwrap();
-        worksheet.set_freeze_panes(row, column).unwrap();
-        Ok(())
+    ///
+    /// ## Raises
+    /// - `ParameterError`: If `segments` is empty or one of its texts is empty
+    /// - `StringLengthError`: If the text exceeds Excel's limit of 32,767 characters
+    pub fn write_rich_string(
+        &mut self,
+        row: RowNum,
+        column: ColNum,
+        segments: RichString,
+        format_option: Option<FormatOption>,
+    ) -> PyResult<()> {
+        self.write(
+            row,
+            column,
+            Some(ValueType::RichString(segments)),
+            None,
+            None,
+            None,
+            format_option,
+        )
     }
-}
+
+    #[pyo3(signature = (row, column, format_option=None))]
+    /// Worksheet handler for writing a "blank" cell.
+    /// This function will only perform write if `format_option` is specified.
+    /// If there is no format option specified, the corresponding cell will be an "empty" cell.
+    ///
+    /// The format stays below the formats of the values written to the cell later, so a border
+    /// drawn with `write_blank` is kept when a value with a different format is written into the cell.
+    ///
+    /// See this [documentation](https://docs.rs/rust_xlsxwriter/0.75.0/rust_xlsxwriter/worksheet/struct.Worksheet.html#method.write_blank) for difference between "blank cell" and "empty cell".
+    ///
+    /// ## Parameters
+    /// - `row`: The row index of the cell
+    /// - `column`: The column index of the cell
+    /// - `format_option`: The format of the cell, or the name of a registered style _(optional)_
+    ///
+    /// ## Examples
+    /// The following example demonstrates writing a "blank" cell to a worksheet.
+    /// ```
+    /// from pyaccelsx import ExcelWorkbook, ExcelFormat
+    ///
+    /// def main():
+    ///     workbook = ExcelWorkbook()
+    ///     workbook.add_worksheet()
+    ///     
+    ///     format_option = ExcelFormat(border=True)
+    ///     workbook.write_blank(0, 0, format_option)
+    ///     // This will not perform any write
+    ///     workbook.write_blank(0, 1)
+    ///
+    ///     workbook.save("example.xlsx")
+    /// ```
+    ///
+    pub fn write_blank(
+        &mut self,
+        row: RowNum,
+        column: ColNum,
+        format_option: Option<FormatOption>,
+    ) -> PyResult<()> {
+        if let Some(format_option) = format_option {
+            let format = self.formats.resolve(&format_option)?;
+            self.write_blank_format(row, column, format)?;
+            self.record_header_write(HeaderWrite::Blank {
+                row,
+                column,
+                format,
+            })?;
+        }
+        Ok(())
+    }
+
+    #[pyo3(signature = (row, column, formula, format_option=None, value=None))]
+    /// Worksheet handler for writing a formula to a cell.
+    ///
+    /// Excel calculates the formula when the workbook is opened. Other applications, such as
+    /// file previewers, show the cached `value` instead, which defaults to `0`.
+    /// Functions added in newer Excel versions, such as `XLOOKUP`, are supported as well,
+    /// in upper or lower case.
+    ///
+    /// Strings written with `write` are always written as text, even if they start with `=`.
+    ///
+    /// ## Parameters
+    /// - `row`: The row index of the cell
+    /// - `column`: The column index of the cell
+    /// - `formula`: The formula, with or without the leading `=`, e.g. `"=SUM(A1:A10)"`
+    /// - `format_option`: The format of the cell, or the name of a registered style _(optional)_
+    /// - `value`: The cached result of the formula _(optional)_
+    ///
+    /// ## Examples
+    /// The following example demonstrates writing a totals row with formulas.
+    /// ```
+    /// from pyaccelsx import ExcelWorkbook, ExcelFormat
+    ///
+    /// def main():
+    ///     workbook = ExcelWorkbook()
+    ///     workbook.add_worksheet()
+    ///
+    ///     workbook.write(0, 0, 10)
+    ///     workbook.write(1, 0, 20)
+    ///     workbook.write_formula(2, 0, "=SUM(A1:A2)", ExcelFormat(bold=True), value=30)
+    ///
+    ///     workbook.save("example.xlsx")
+    /// ```
+    ///
+    /// ## Raises
+    /// - `RowColumnLimitError`: If the cell is outside of the worksheet limits
+    /// - `RowOrderError`: If a row is above the current row of a `constant_memory` worksheet
+    pub fn write_formula(
+        &mut self,
+        row: RowNum,
+        column: ColNum,
+        formula: String,
+        format_option: Option<FormatOption>,
+        value: Option<FormulaResult>,
+    ) -> PyResult<()> {
+        let format = match &format_option {
+            Some(format_option) => Some(self.formats.resolve(format_option)?),
+            None => None,
+        };
+        let formula = new_formula(formula, value);
+        self.write_formula_format(row, column, formula.clone(), format)?;
+        self.record_header_write(HeaderWrite::Formula {
+            row,
+            column,
+            formula,
+            format,
+        })
+    }
+
+    #[pyo3(signature = (start_row, start_column, end_row, end_column, formula, format_option=None, value=None))]
+    /// Worksheet handler for writing an array formula (also known as a CSE formula) to a range of cells.
+    ///
+    /// ## Parameters
+    /// - `start_row`: The start row index of the range
+    /// - `start_column`: The start column index of the range
+    /// - `end_row`: The end row index of the range
+    /// - `end_column`: The end column index of the range
+    /// - `formula`: The formula, with or without the leading `=` and the surrounding braces
+    /// - `format_option`: The format of the range, or the name of a registered style _(optional)_
+    /// - `value`: The cached result of the formula in the first cell _(optional)_
+    ///
+    /// ## Examples
+    /// The following example demonstrates writing an array formula.
+    /// ```
+    /// from pyaccelsx import ExcelWorkbook
+    ///
+    /// def main():
+    ///     workbook = ExcelWorkbook()
+    ///     workbook.add_worksheet()
+    ///
+    ///     workbook.write(0, 0, 2)
+    ///     workbook.write(1, 0, 3)
+    ///     workbook.write(0, 1, 4)
+    ///     workbook.write(1, 1, 5)
+    ///     workbook.write_array_formula(0, 2, 1, 2, "{=A1:A2*B1:B2}")
+    ///
+    ///     workbook.save("example.xlsx")
+    /// ```
+    ///
+    /// ## Raises
+    /// - `RowColumnLimitError`: If the range is outside of the worksheet limits
+    /// - `ParameterError`: If the range crosses the last row, or the last header row, of a worksheet
+    ///   with `overflow="new_sheet"`
+    /// - `RowOrderError`: If a row is above the current row of a `constant_memory` worksheet
+    #[allow(clippy::too_many_arguments)]
+    pub fn write_array_formula(
+        &mut self,
+        start_row: RowNum,
+        start_column: ColNum,
+        end_row: RowNum,
+        end_column: ColNum,
+        formula: String,
+        format_option: Option<FormatOption>,
+        value: Option<FormulaResult>,
+    ) -> PyResult<()> {
+        self.write_range_formula(
+            (start_row, start_column),
+            (end_row, end_column),
+            new_formula(formula, value),
+            format_option,
+            false,
+        )
+    }
+
+    #[pyo3(signature = (start_row, start_column, end_row, end_column, formula, format_option=None, value=None))]
+    /// Worksheet handler for writing a dynamic array formula, such as `FILTER` or `UNIQUE`,
+    /// which spills its results into the cells below and to the right of it.
+    ///
+    /// ## Parameters
+    /// - `start_row`: The start row index of the range
+    /// - `start_column`: The start column index of the range
+    /// - `end_row`: The end row index of the range, usually the same as `start_row`
+    /// - `end_column`: The end column index of the range, usually the same as `start_column`
+    /// - `formula`: The formula, with or without the leading `=`
+    /// - `format_option`: The format of the range, or the name of a registered style _(optional)_
+    /// - `value`: The cached result of the formula in the first cell _(optional)_
+    ///
+    /// ## Examples
+    /// The following example demonstrates writing a dynamic array formula.
+    /// ```
+    /// from pyaccelsx import ExcelWorkbook
+    ///
+    /// def main():
+    ///     workbook = ExcelWorkbook()
+    ///     workbook.add_worksheet()
+    ///
+    ///     for row, carrier in enumerate(["Maersk", "MSC", "Maersk"]):
+    ///         workbook.write(row, 0, carrier)
+    ///     workbook.write_dynamic_array_formula(0, 1, 0, 1, "=UNIQUE(A1:A3)")
+    ///
+    ///     workbook.save("example.xlsx")
+    /// ```
+    ///
+    /// ## Raises
+    /// - `RowColumnLimitError`: If the range is outside of the worksheet limits
+    /// - `ParameterError`: If the range crosses the last row, or the last header row, of a worksheet
+    ///   with `overflow="new_sheet"`
+    /// - `RowOrderError`: If a row is above the current row of a `constant_memory` worksheet
+    #[allow(clippy::too_many_arguments)]
+    pub fn write_dynamic_array_formula(
+        &mut self,
+        start_row: RowNum,
+        start_column: ColNum,
+        end_row: RowNum,
+        end_column: ColNum,
+        formula: String,
+        format_option: Option<FormatOption>,
+        value: Option<FormulaResult>,
+    ) -> PyResult<()> {
+        self.write_range_formula(
+            (start_row, start_column),
+            (end_row, end_column),
+            new_formula(formula, value),
+            format_option,
+            true,
+        )
+    }
+
+    #[pyo3(signature = (row, column, url, text=None, tip=None, format_option=None))]
+    /// Worksheet handler for writing a hyperlink to a cell.
+    ///
+    /// The following kinds of links are supported:
+    /// - Web and email links, starting with `http://`, `https://`, `ftp://`, `ftps://` or `mailto:`
+    /// - Local file links, such as `file:///C:/Temp/Book1.xlsx` or `file:///Book2.xlsx#Sheet1!A1`
+    /// - Links to a cell of the workbook, such as `internal:Sheet2!A1` or `internal:'Sales Data'!A1`
+    ///
+    /// Without a `format_option`, the cell gets Excel's blue underlined "Hyperlink" style.
+    ///
+    /// ## Parameters
+    /// - `row`: The row index of the cell
+    /// - `column`: The column index of the cell
+    /// - `url`: The target of the link
+    /// - `text`: The text shown in the cell, which defaults to the `url` _(optional)_
+    /// - `tip`: The text shown when hovering over the link _(optional)_
+    /// - `format_option`: The format of the cell, or the name of a registered style _(optional)_
+    ///
+    /// ## Examples
+    /// The following example demonstrates writing a link to a carrier portal and to another worksheet.
+    /// ```
+    /// from pyaccelsx import ExcelWorkbook
+    ///
+    /// def main():
+    ///     workbook = ExcelWorkbook()
+    ///     workbook.add_worksheet("Shipments")
+    ///     workbook.add_worksheet("Summary")
+    ///
+    ///     workbook.write_url(0, 0, "https://www.maersk.com/tracking/MSKU1234567", text="Track")
+    ///     workbook.write_url(1, 0, "mailto:ops@example.com", tip="Email operations")
+    ///     workbook.write_url(2, 0, "internal:Shipments!A1", text="Back to shipments")
+    ///
+    ///     workbook.save("example.xlsx")
+    /// ```
+    ///
+    /// ## Raises
+    /// - `HyperlinkError`: If the link type is unsupported, the link is longer than
+    ///   Excel's limit of 2,080 characters, or the worksheet already has 65,530 hyperlinks
+    /// - `ParameterError`: If the tip is longer than 255 characters
+    pub fn write_url(
+        &mut self,
+        row: RowNum,
+        column: ColNum,
+        url: String,
+        text: Option<String>,
+        tip: Option<String>,
+        format_option: Option<FormatOption>,
+    ) -> PyResult<()> {
+        let format = match &format_option {
+            Some(format_option) => Some(self.formats.resolve(format_option)?),
+            None => None,
+        };
+        self.write_url_format(row, column, url.clone(), text.clone(), tip.clone(), format)?;
+        self.record_header_write(HeaderWrite::Url {
+            row,
+            column,
+            url,
+            text,
+            tip,
+            format,
+        })
+    }
+
+    #[pyo3(signature = (start_row, start_column, end_row, end_column, format_option=None))]
+    /// Worksheet handler for merging a range of cells. This will not do any
+    /// writing to the cell values. To write values, use `write_and_merge_range`.
+    ///
+    /// ## Parameters
+    /// - `start_row`: The start row index of the range
+    /// - `start_column`: The start column index of the range
+    /// - `end_row`: The end row index of the range
+    /// - `end_column`: The end column index of the range
+    /// - `format_option`: The format of the cell, or the name of a registered style _(optional)_
+    ///
+    /// ## Examples
+    /// The following example demonstrates merging cells in a worksheet.
+    /// ```
+    /// from pyaccelsx import ExcelWorkbook, ExcelFormat
+    ///
+    /// def main():
+    ///     workbook = ExcelWorkbook()
+    ///     workbook.add_worksheet()
+    ///     
+    ///     format_option = ExcelFormat(align="center", border=True)
+    ///     workbook.merge_range(0, 0, 0, 2, format_option)
+    ///
+    ///     workbook.save("example.xlsx")
+    /// ```
+    ///
+    /// ## Raises
+    /// - `ParameterError`: If the range crosses the last row, or the last header row, of a worksheet
+    ///   with `overflow="new_sheet"`
+    pub fn merge_range(
+        &mut self,
+        start_row: RowNum,
+        start_column: ColNum,
+        end_row: RowNum,
+        end_column: ColNum,
+        format_option: Option<FormatOption>,
+    ) -> PyResult<()> {
+        let format = match format_option {
+            Some(format_option) => Some(self.formats.resolve(&format_option)?),
+            None => None,
+        };
+        let (first, last) = ((start_row, start_column), (end_row, end_column));
+        self.check_header_range(first, last)?;
+        self.merge_range_format(first, last, format)?;
+        self.record_header_write(HeaderWrite::Merge {
+            first,
+            last,
+            format,
+        })
+    }
+
+    #[pyo3(signature = (start_row, start_column, end_row, end_column, value=None, override_true_value=None, override_false_value=None, override_value=None, format_option=None))]
+    /// Worksheet handler for merging a range of cells and writing string value into the merged cells.
+    ///
+    /// ## Parameters
+    /// - `start_row`: The start row index of the range
+    /// - `start_column`: The start column index of the range
+    /// - `end_row`: The end row index of the range
+    /// - `end_column`: The end column index of the range
+    /// - `value`: The value to write, which can also be a rich string, see `write_rich_string`
+    /// - `override_true_value`: The string value to write if the cell value is `True` _(optional)_
+    /// - `override_false_value`: The string value to write if the cell value is `False` _(optional)_
+    /// - `override_value`: The string value to write if the cell value is not `True` or `False` _(optional)_
+    /// - `format_option`: The format of the cell, or the name of a registered style _(optional)_
+    ///
+    /// ## Examples
+    /// The following example demonstrates merging cells and writing string value into the merged cells in a worksheet.
+    /// ```
+    /// from pyaccelsx import ExcelWorkbook, ExcelFormat
+    ///
+    /// def main():
+    ///     workbook = ExcelWorkbook()
+    ///     workbook.add_worksheet()
+    ///     
+    ///     format_option = ExcelFormat(align="center", border=True)
+    ///     workbook.write_and_merge_range(0, 0, 0, 2, "Hello World!", format_option)
+    ///
+    ///     workbook.save("example.xlsx")
+    /// ```
+    #[allow(clippy::too_many_arguments)]
+    pub fn write_and_merge_range(
+        &mut self,
+        start_row: RowNum,
+        start_column: ColNum,
+        end_row: RowNum,
+        end_column: ColNum,
+        value: Option<ValueType>,
+        override_true_value: Option<String>,
+        override_false_value: Option<String>,
+        override_value: Option<String>,
+        format_option: Option<FormatOption>,
+    ) -> PyResult<()> {
+        if let Some(value) = value {
+            // Prevent using moved value
+            let cloned_format_option = format_option.clone();
+            self.merge_range(start_row, start_column, end_row, end_column, format_option)?;
+            self.write(
+                start_row,
+                start_column,
+                Some(value),
+                override_true_value,
+                override_false_value,
+                override_value,
+                cloned_format_option,
+            )?;
+        } else {
+            self.merge_range(start_row, start_column, end_row, end_column, format_option)?;
+        }
+        Ok(())
+    }
+
+    /// Worksheet handler for setting the format of a row.
+    ///
+    /// Formats are layered: a cell shows the options of its column format, overridden by
+    /// its row format, then by the range formats covering it, then by the format of
+    /// `write_blank`, then by the format written to the cell itself, then by the borders
+    /// of `draw_border`. Setting the format
+    /// of a row again adds to its previous format, while writing a cell again replaces
+    /// the format written to it before.
+    ///
+    /// Cells written before or after the row format keep their values and get the combined format.
+    ///
+    /// ## Parameters
+    /// - `row`: The row index
+    /// - `format_option`: The format of the row, or the name of a registered style
+    ///
+    /// ## Examples
+    /// The following example demonstrates making a header row bold.
+    /// ```
+    /// from pyaccelsx import ExcelWorkbook, ExcelFormat
+    ///
+    /// def main():
+    ///     workbook = ExcelWorkbook()
+    ///     workbook.add_worksheet()
+    ///
+    ///     workbook.set_row_format(0, ExcelFormat(bold=True))
+    ///     // This cell is bold and right aligned
+    ///     workbook.write(0, 0, "Amount", format_option=ExcelFormat(align="right"))
+    ///
+    ///     workbook.save("example.xlsx")
+    /// ```
+    ///
+    /// ## Raises
+    /// - `RowColumnLimitError`: If the row is outside of the worksheet limits
+    /// - `RowOrderError`: If the first row is above the current row of a `constant_memory` worksheet
+    pub fn set_row_format(&mut self, row: RowNum, format_option: FormatOption) -> PyResult<()> {
+        let format = self.formats.resolve(&format_option)?;
+        self.set_row_format_id(row, format)?;
+        self.record_header_write(HeaderWrite::RowFormat { row, format })
+    }
+
+    /// Worksheet handler for setting the format of a column.
+    ///
+    /// Setting the format of a column again adds to its previous format.
+    /// See `set_row_format` for how the formats of columns, rows, ranges and cells are combined.
+    /// On a `constant_memory` worksheet, the cells of the rows already written keep their format.
+    ///
+    /// ## Parameters
+    /// - `column`: The column index
+    /// - `format_option`: The format of the column, or the name of a registered style
+    ///
+    /// ## Examples
+    /// The following example demonstrates setting the number format of a column.
+    /// ```
+    /// from pyaccelsx import ExcelWorkbook, ExcelFormat
+    ///
+    /// def main():
+    ///     workbook = ExcelWorkbook()
+    ///     workbook.add_worksheet()
+    ///
+    ///     workbook.set_column_format(1, ExcelFormat(num_format="#,##0.00"))
+    ///     workbook.write(0, 1, 1234.5)
+    ///
+    ///     workbook.save("example.xlsx")
+    /// ```
+    ///
+    /// ## Raises
+    /// - `RowColumnLimitError`: If the column is outside of the worksheet limits
+    pub fn set_column_format(
+        &mut self,
+        column: ColNum,
+        format_option: FormatOption,
+    ) -> PyResult<()> {
+        let worksheet = worksheet_at(&mut self.workbook, self.active_worksheet_index)?;
+        let format = self.formats.resolve(&format_option)?;
+        self.layers[self.active_worksheet_index].set_column(
+            worksheet,
+            &mut self.formats,
+            column,
+            format,
+        )?;
+        if let Some(rollover) = self.active_rollover() {
+            rollover.column_formats.push((column, format));
+        }
+        Ok(())
+    }
+
+    /// Worksheet handler for setting the format of a range of cells, without changing their values.
+    ///
+    /// The format is added on top of the formats of the cells in the range, and is kept for
+    /// values written into the range later. Empty cells in the range are left empty, so that a
+    /// range of any size is cheap to format. See `set_row_format` for how the formats of columns,
+    /// rows, ranges and cells are combined.
+    ///
+    /// ## Parameters
+    /// - `start_row`: The start row index of the range
+    /// - `start_column`: The start column index of the range
+    /// - `end_row`: The end row index of the range
+    /// - `end_column`: The end column index of the range
+    /// - `format_option`: The format of the range, or the name of a registered style
+    ///
+    /// ## Examples
+    /// The following example demonstrates shading a table before writing its values.
+    /// ```
+    /// from pyaccelsx import ExcelWorkbook, ExcelFormat
+    ///
+    /// def main():
+    ///     workbook = ExcelWorkbook()
+    ///     workbook.add_worksheet()
+    ///
+    ///     workbook.set_range_format(0, 0, 9, 2, ExcelFormat(bg_color="F2F2F2"))
+    ///     // This cell is shaded and bold
+    ///     workbook.write(0, 0, "Name", format_option=ExcelFormat(bold=True))
+    ///
+    ///     workbook.save("example.xlsx")
+    /// ```
+    ///
+    /// ## Raises
+    /// - `RowColumnLimitError`: If the range is outside of the worksheet limits
+    /// - `ParameterError`: If the range crosses the last row of a worksheet with `overflow="new_sheet"`
+    /// - `RowOrderError`: If the first row is above the current row of a `constant_memory` worksheet
+    pub fn set_range_format(
+        &mut self,
+        start_row: RowNum,
+        start_column: ColNum,
+        end_row: RowNum,
+        end_column: ColNum,
+        format_option: FormatOption,
+    ) -> PyResult<()> {
+        let format = self.formats.resolve(&format_option)?;
+        let (first, last) = ((start_row, start_column), (end_row, end_column));
+        self.set_range_format_id(first, last, format)?;
+        self.record_header_write(HeaderWrite::RangeFormat {
+            first,
+            last,
+            format,
+        })
+    }
+
+    #[pyo3(signature = (start_row, start_column, end_row, end_column, style=BorderOption::Style("thin".to_string()), color=None, inner=None))]
+    /// Worksheet handler for drawing a border around a range of cells, and optionally
+    /// between the cells of the range.
+    ///
+    /// The borders are added to the formats of the cells, so their values, number formats,
+    /// fills and other options are kept, and values written into the range later keep the borders.
+    /// The outer border is drawn on empty cells too, while the inner borders are only added to the
+    /// cells that are written, like the formats of `set_range_format`.
+    ///
+    /// ## Parameters
+    /// - `start_row`: The start row index of the range
+    /// - `start_column`: The start column index of the range
+    /// - `end_row`: The end row index of the range
+    /// - `end_column`: The end column index of the range
+    /// - `style`: The style of the outer border, such as `"thin"` (default), `"medium"` or `"double"`
+    /// - `color`: The hex color of the borders, e.g. `"FF0000"` _(optional)_
+    /// - `inner`: The style of the borders between the cells of the range _(optional)_
+    ///
+    /// ## Examples
+    /// The following example demonstrates drawing a box around a table.
+    /// ```
+    /// from pyaccelsx import ExcelWorkbook, ExcelFormat
+    ///
+    /// def main():
+    ///     workbook = ExcelWorkbook()
+    ///     workbook.add_worksheet()
+    ///
+    ///     workbook.write(0, 0, "Name", format_option=ExcelFormat(bold=True))
+    ///     workbook.write(0, 1, "Amount", format_option=ExcelFormat(bold=True))
+    ///     workbook.write(1, 0, "Freight")
+    ///     workbook.write(1, 1, 1234.5, format_option=ExcelFormat(num_format="#,##0.00"))
+    ///
+    ///     workbook.draw_border(0, 0, 3, 1, style="medium", inner="hair")
+    ///
+    ///     workbook.save("example.xlsx")
+    /// ```
+    ///
+    /// ## Raises
+    /// - `ValueError`: If the style or color is invalid
+    /// - `RowColumnLimitError`: If the range is outside of the worksheet limits
+    /// - `ParameterError`: If the range crosses the last row of a worksheet with `overflow="new_sheet"`
+    /// - `RowOrderError`: If the first row is above the current row of a `constant_memory` worksheet
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_border(
+        &mut self,
+        start_row: RowNum,
+        start_column: ColNum,
+        end_row: RowNum,
+        end_column: ColNum,
+        style: BorderOption,
+        color: Option<String>,
+        inner: Option<BorderOption>,
+    ) -> PyResult<()> {
+        // The options are validated before the rows are located, which can add worksheets.
+        ExcelFormat::validate_border(&style, color.as_ref(), self.formats.strict())?;
+        if let Some(inner) = &inner {
+            ExcelFormat::validate_border(inner, None, self.formats.strict())?;
+        }
+        // The whole range is located first, so that no border is drawn if it crosses worksheets.
+        self.range_segment((start_row, start_column), end_row)?;
+        let (first_row, last_row) = (start_row.min(end_row), start_row.max(end_row));
+        let (first_column, last_column) =
+            (start_column.min(end_column), start_column.max(end_column));
+
+        if let Some(inner) = &inner {
+            let grid = self
+                .formats
+                .id(&ExcelFormat::borders(inner, color.as_ref(), [true; 4]));
+            let (first, last) = ((first_row, first_column), (last_row, last_column));
+            self.add_format(first, last, grid, true)?;
+            self.record_header_write(HeaderWrite::AddedFormat {
+                first,
+                last,
+                format: grid,
+                lazy: true,
+            })?;
+        }
+
+        // The outer border is drawn last, so that it replaces the inner border on the edges.
+        let edges = [
+            (
+                (first_row, first_column),
+                (first_row, last_column),
+                [true, false, false, false],
+            ),
+            (
+                (last_row, first_column),
+                (last_row, last_column),
+                [false, true, false, false],
+            ),
+            (
+                (first_row, first_column),
+                (last_row, first_column),
+                [false, false, true, false],
+            ),
+            (
+                (first_row, last_column),
+                (last_row, last_column),
+                [false, false, false, true],
+            ),
+        ];
+        for (first, last, sides) in edges {
+            let edge = self
+                .formats
+                .id(&ExcelFormat::borders(&style, color.as_ref(), sides));
+            self.add_format(first, last, edge, false)?;
+            self.record_header_write(HeaderWrite::AddedFormat {
+                first,
+                last,
+                format: edge,
+                lazy: false,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Worksheet handler for setting column width.
+    ///
+    /// ## Parameters
+    /// - `column`: The column index of the cell
+    /// - `width`: The width of the column
+    ///
+    /// ## Examples
+    /// The following example demonstrates setting column width in a worksheet.
+    /// ```
+    /// from pyaccelsx import ExcelWorkbook
+    ///
+    /// def main():
+    ///     workbook = ExcelWorkbook()
+    ///     workbook.add_worksheet()
+    ///
+    ///     workbook.write(0, 0, "Hello World!")
+    ///     workbook.set_column_width(0, 20)
+    ///
+    ///     workbook.save("example.xlsx")
+    /// ```
+    pub fn set_column_width(&mut self, column: ColNum, width: f64) -> PyResult<()> {
+        let worksheet = worksheet_at(&mut self.workbook, self.active_worksheet_index)?;
+        let result = worksheet.set_column_width(column, width).map(|_| ());
+        result.map_err(|error| errors::to_py_err(error, Some(worksheet.name().as_str()), None))?;
+        if let Some(rollover) = self.active_rollover() {
+            rollover.column_widths.push((column, width));
+        }
+        Ok(())
+    }
+
+    /// Worksheet handler for freezing panes.
+    ///
+    /// ## Parameters
+    /// - `row`: The row index of the cell which will be frozen
+    /// - `column`: The column index of the cell which will be frozen
+    ///
+    /// ## Examples
+    /// The following example demonstrates freezing panes in a worksheet.
+    /// ```
+    /// from pyaccelsx import ExcelWorkbook
+    ///
+    /// def main():
+    ///     workbook = ExcelWorkbook()
+    ///     workbook.add_worksheet()
+    ///
+    ///     workbook.write(0, 0, "Hello World!")
+    ///     // This freezes the first row and first column
+    ///     workbook.freeze_panes(0, 0)
+    ///
+    ///     workbook.save("example.xlsx")
+    /// ```
+    pub fn freeze_panes(&mut self, row: RowNum, column: ColNum) -> PyResult<()> {
+        let worksheet = worksheet_at(&mut self.workbook, self.active_worksheet_index)?;
+        let result = worksheet.set_freeze_panes(row, column).map(|_| ());
+        result.map_err(|error| {
+            errors::to_py_err(error, Some(worksheet.name().as_str()), Some((row, column)))
+        })?;
+        if let Some(rollover) = self.active_rollover() {
+            rollover.freeze_panes = Some((row, column));
+        }
+        Ok(())
+    }
+
+    #[pyo3(signature = (password=None, options=None))]
+    /// Worksheet handler for protecting the worksheet from changes.
+    ///
+    /// Only cells with a format of `ExcelFormat(locked=False)` can be edited on a
+    /// protected worksheet, and cells with `ExcelFormat(hidden=True)` hide their formulas.
+    ///
+    /// ## Parameters
+    /// - `password`: The password required to unprotect the worksheet _(optional)_
+    /// - `options`: The actions still allowed on the protected worksheet _(optional)_
+    ///
+    /// ## Examples
+    /// The following example demonstrates protecting a worksheet with one editable column.
+    /// ```
+    /// from pyaccelsx import ExcelWorkbook, ExcelFormat, ExcelProtectionOptions
+    ///
+    /// def main():
+    ///     workbook = ExcelWorkbook()
+    ///     workbook.add_worksheet()
+    ///
+    ///     workbook.write(0, 0, "Quantity")
+    ///     workbook.write(0, 1, 10, format_option=ExcelFormat(locked=False))
+    ///
+    ///     options = ExcelProtectionOptions(sort=True, format_columns=True)
+    ///     workbook.protect_worksheet(password="secret", options=options)
+    ///
+    ///     workbook.save("example.xlsx")
+    /// ```
+    pub fn protect_worksheet(
+        &mut self,
+        password: Option<String>,
+        options: Option<ExcelProtectionOptions>,
+    ) -> PyResult<()> {
+        let worksheet = worksheet_at(&mut self.workbook, self.active_worksheet_index)?;
+        match password {
+            Some(password) => worksheet.protect_with_password(&password),
+            None => worksheet.protect(),
+        };
+        if let Some(options) = options {
+            worksheet.protect_with_options(&(&options).into());
+        }
+        Ok(())
+    }
+}
+
+impl ExcelWorkbook {
+    /// Adds a worksheet and makes it the active worksheet, in constant memory mode if enabled.
+    fn push_worksheet(&mut self, name: Option<String>) -> PyResult<()> {
+        match name {
+            None if self.constant_memory => {
+                self.workbook.add_worksheet_with_constant_memory();
+            }
+            None => {
+                self.workbook.add_worksheet();
+            }
+            Some(name) => {
+                // Validate the name before adding the worksheet, so that a
+                // rejected name does not leave an unnamed worksheet behind.
+                let mut worksheet = if self.constant_memory {
+                    self.workbook.new_worksheet_with_constant_memory()
+                } else {
+                    Worksheet::new()
+                };
+                worksheet
+                    .set_name(&name)
+                    .map_err(|error| errors::to_py_err(error, Some(name.as_str()), None))?;
+                let is_reused = self
+                    .workbook
+                    .worksheets()
+                    .iter()
+                    .any(|existing| existing.name().to_lowercase() == name.to_lowercase());
+                if is_reused {
+                    return Err(errors::to_py_err(
+                        XlsxError::SheetnameReused(name.clone()),
+                        Some(name.as_str()),
+                        None,
+                    ));
+                }
+                self.workbook.push_worksheet(worksheet);
+            }
+        }
+        self.layers.push(if self.constant_memory {
+            FormatLayers::with_constant_memory()
+        } else {
+            FormatLayers::default()
+        });
+        self.hyperlinks.push(HashSet::new());
+        self.rollovers.push(None);
+        self.active_worksheet_index = self.workbook.worksheets().len() - 1;
+        Ok(())
+    }
+
+    /// Returns the rollover of the active worksheet, if it was added with `overflow="new_sheet"`.
+    fn active_rollover(&mut self) -> Option<&mut Rollover> {
+        self.rollovers
+            .get_mut(self.active_worksheet_index)
+            .and_then(Option::as_mut)
+    }
+
+    /// Returns the index of the worksheet of a row of the active worksheet, and the row
+    /// index in that worksheet. Continuation worksheets are added as rows reach them.
+    fn locate(&mut self, row: RowNum) -> PyResult<(usize, RowNum)> {
+        let segment = self.segment(row)?;
+        Ok((segment.index, segment.row(row)))
+    }
+
+    /// Returns the worksheet that a row of the active worksheet is written to, along
+    /// with the other rows written to the same worksheet.
+    fn segment(&mut self, row: RowNum) -> PyResult<Segment> {
+        let root = self.active_worksheet_index;
+        let Some(mut rollover) = self.rollovers.get_mut(root).and_then(Option::take) else {
+            return Ok(Segment {
+                index: root,
+                rows: 0..u64::MAX,
+                first_row: 0,
+                is_root: false,
+            });
+        };
+        let result = self.rollover_segment(&mut rollover, row);
+        self.rollovers[root] = Some(rollover);
+        result
+    }
+
+    /// Returns the segment of a range of rows of the active worksheet, from the row of
+    /// `first` to `last_row` in either order. Ranges cannot be split across worksheets,
+    /// so a range crossing the last row of a worksheet with a rollover is rejected.
+    fn range_segment(&mut self, first: (RowNum, ColNum), last_row: RowNum) -> PyResult<Segment> {
+        let segment = self.segment(first.0.min(last_row))?;
+        if segment.contains(first.0.max(last_row)) {
+            return Ok(segment);
+        }
+        let error = XlsxError::ParameterError(format!(
+            "The range from row {} to row {last_row} crosses the last row of a worksheet with overflow='new_sheet'. Merges, array formulas, range formats and borders must fit in a single worksheet.",
+            first.0
+        ));
+        let worksheet = worksheet_at(&mut self.workbook, segment.index)?;
+        Err(errors::to_py_err(
+            error,
+            Some(worksheet.name().as_str()),
+            Some(first),
+        ))
+    }
+
+    /// Returns the segment of a row of the active worksheet, which has been taken out of
+    /// `rollovers` so that its continuation worksheets can be added.
+    fn rollover_segment(&mut self, rollover: &mut Rollover, row: RowNum) -> PyResult<Segment> {
+        let (part, _) = rollover.locate(row);
+        while rollover.continuations.len() < part {
+            self.add_continuation(rollover)?;
+        }
+        let (rows, first_row) = rollover.rows(part);
+        Ok(Segment {
+            index: match part {
+                0 => self.active_worksheet_index,
+                part => rollover.continuations[part - 1].index,
+            },
+            rows,
+            first_row,
+            is_root: part == 0,
+        })
+    }
+
+    /// Adds the next continuation worksheet of the active worksheet, with its header rows,
+    /// column widths, frozen panes, and column and header row formats.
+    fn add_continuation(&mut self, rollover: &mut Rollover) -> PyResult<()> {
+        let root = self.active_worksheet_index;
+        let name = rollover.continuation_name(rollover.continuations.len() + 1);
+        self.push_worksheet(Some(name))?;
+        let index = self.active_worksheet_index;
+        let result = self.copy_rollover(index, rollover);
+        self.active_worksheet_index = root;
+        rollover.push_continuation(index);
+        result
+    }
+
+    /// Writes the header rows and settings of a rollover to the active worksheet.
+    fn copy_rollover(&mut self, index: usize, rollover: &mut Rollover) -> PyResult<()> {
+        let worksheet = worksheet_at(&mut self.workbook, index)?;
+        if let Some((row, column)) = rollover.freeze_panes {
+            worksheet
+                .set_freeze_panes(row, column)
+                .map_err(|error| errors::to_py_err(error, None, Some((row, column))))?;
+        }
+        for &(column, width) in &rollover.column_widths {
+            worksheet
+                .set_column_width(column, width)
+                .map_err(|error| errors::to_py_err(error, None, None))?;
+        }
+        for &(column, format) in &rollover.column_formats {
+            self.layers[index].set_column(worksheet, &mut self.formats, column, format)?;
+        }
+        // Constant memory worksheets are written in row order.
+        let header_rows = rollover.header_rows();
+        let mut header_writes: Vec<_> = rollover
+            .header_writes
+            .iter()
+            .filter(|write| write.row() < header_rows)
+            .collect();
+        header_writes.sort_by_key(|write| write.row());
+        for write in header_writes {
+            self.apply_header_write(write)?;
+        }
+        Ok(())
+    }
+
+    /// Records a write to the header rows of the active worksheet, if it has a rollover,
+    /// and writes it to its continuation worksheets.
+    fn record_header_write(&mut self, write: HeaderWrite) -> PyResult<()> {
+        if let Some(rollover) = self.active_rollover() {
+            if let Some(write) = write.clip(rollover.header_rows()) {
+                rollover.header_writes.push(write);
+            }
+        }
+        self.repeat_header_writes()
+    }
+
+    /// Writes the header writes made since the continuation worksheets of the active
+    /// worksheet were added to them, so that they keep the header rows of the worksheet.
+    fn repeat_header_writes(&mut self) -> PyResult<()> {
+        let root = self.active_worksheet_index;
+        let Some(mut rollover) = self.rollovers.get_mut(root).and_then(Option::take) else {
+            return Ok(());
+        };
+        let header_rows = rollover.header_rows();
+        let mut result = Ok(());
+        for continuation in &mut rollover.continuations {
+            self.active_worksheet_index = continuation.index;
+            result = rollover.header_writes[continuation.replayed..]
+                .iter()
+                .filter(|write| write.row() < header_rows)
+                .try_for_each(|write| self.apply_header_write(write));
+            continuation.replayed = rollover.header_writes.len();
+            if result.is_err() {
+                break;
+            }
+        }
+        self.active_worksheet_index = root;
+        self.rollovers[root] = Some(rollover);
+        result
+    }
+
+    /// Writes a recorded header write to the active worksheet.
+    fn apply_header_write(&mut self, write: &HeaderWrite) -> PyResult<()> {
+        match write.clone() {
+            HeaderWrite::Value {
+                row,
+                column,
+                value,
+                overrides,
+                format,
+            } => self.write_value(row, column, value, &overrides, format),
+            HeaderWrite::Blank {
+                row,
+                column,
+                format,
+            } => self.write_blank_format(row, column, format),
+            HeaderWrite::Formula {
+                row,
+                column,
+                formula,
+                format,
+            } => self.write_formula_format(row, column, formula, format),
+            HeaderWrite::RangeFormula {
+                first,
+                last,
+                formula,
+                format,
+                dynamic,
+            } => self.write_range_formula_format(first, last, formula, format, dynamic),
+            HeaderWrite::Url {
+                row,
+                column,
+                url,
+                text,
+                tip,
+                format,
+            } => self.write_url_format(row, column, url, text, tip, format),
+            HeaderWrite::Merge {
+                first,
+                last,
+                format,
+            } => self.merge_range_format(first, last, format),
+            HeaderWrite::RowFormat { row, format } => self.set_row_format_id(row, format),
+            HeaderWrite::RangeFormat {
+                first,
+                last,
+                format,
+            } => self.set_range_format_id(first, last, format),
+            HeaderWrite::AddedFormat {
+                first,
+                last,
+                format,
+                lazy,
+            } => self.add_format(first, last, format, lazy),
+        }
+    }
+
+    /// Rejects a merge or an array formula crossing the last header row of a worksheet with
+    /// a rollover, which cannot be repeated on the continuation worksheets.
+    fn check_header_range(
+        &mut self,
+        first: (RowNum, ColNum),
+        last: (RowNum, ColNum),
+    ) -> PyResult<()> {
+        let Some(rollover) = self.active_rollover() else {
+            return Ok(());
+        };
+        let header_rows = rollover.header_rows();
+        let (first_row, last_row) = (first.0.min(last.0), first.0.max(last.0));
+        if first_row >= header_rows || last_row < header_rows {
+            return Ok(());
+        }
+        let error = XlsxError::ParameterError(format!(
+            "The range from row {} to row {} crosses the last header row of a worksheet with overflow='new_sheet'. Merges and array formulas must fit in the {header_rows} header rows to be repeated on the new worksheets.",
+            first.0, last.0
+        ));
+        let worksheet = worksheet_at(&mut self.workbook, self.active_worksheet_index)?;
+        Err(errors::to_py_err(
+            error,
+            Some(worksheet.name().as_str()),
+            Some(first),
+        ))
+    }
+
+    /// Writes a blank cell with the given format, kept below the values written to the cell later.
+    fn write_blank_format(
+        &mut self,
+        row: RowNum,
+        column: ColNum,
+        format: FormatId,
+    ) -> PyResult<()> {
+        let (index, row) = self.locate(row)?;
+        let worksheet = worksheet_at(&mut self.workbook, index)?;
+        let format =
+            self.layers[index].write_blank(worksheet, &mut self.formats, row, column, format)?;
+        let result = worksheet
+            .write_blank(row, column, self.formats.format(format))
+            .map(|_| ());
+        result.map_err(|error| {
+            errors::to_py_err(error, Some(worksheet.name().as_str()), Some((row, column)))
+        })
+    }
+
+    /// Writes a formula with the given format.
+    fn write_formula_format(
+        &mut self,
+        row: RowNum,
+        column: ColNum,
+        formula: Formula,
+        format: Option<FormatId>,
+    ) -> PyResult<()> {
+        let (index, row) = self.locate(row)?;
+        let worksheet = worksheet_at(&mut self.workbook, index)?;
+        let format = self.layers[index]
+            .write_cell(worksheet, &mut self.formats, row, column, None, format)?
+            .map(|format| self.formats.format(format));
+        writer::write_formula(worksheet, row, column, formula, format)
+    }
+
+    /// Writes a hyperlink with the given format.
+    fn write_url_format(
+        &mut self,
+        row: RowNum,
+        column: ColNum,
+        url: String,
+        text: Option<String>,
+        tip: Option<String>,
+        format: Option<FormatId>,
+    ) -> PyResult<()> {
+        let (index, row) = self.locate(row)?;
+        let worksheet = worksheet_at(&mut self.workbook, index)?;
+        let hyperlink_format = *self
+            .hyperlink_format
+            .get_or_insert_with(|| self.formats.id(&ExcelFormat::hyperlink()));
+        // Keep the native "Hyperlink" style of `rust_xlsxwriter` when no other format applies.
+        let format = self.layers[index]
+            .write_cell(
+                worksheet,
+                &mut self.formats,
+                row,
+                column,
+                Some(hyperlink_format),
+                format,
+            )?
+            .filter(|&format| format != hyperlink_format)
+            .map(|format| self.formats.format(format));
+
+        let mut url = Url::new(url);
+        if let Some(text) = text {
+            url = url.set_text(text);
+        }
+        if let Some(tip) = tip {
+            url = url.set_tip(tip);
+        }
+        writer::write_url(
+            worksheet,
+            row,
+            column,
+            url,
+            format,
+            &mut self.hyperlinks[index],
+        )
+    }
+    /// Merges a range of cells with the given format.
+    fn merge_range_format(
+        &mut self,
+        (start_row, start_column): (RowNum, ColNum),
+        (end_row, end_column): (RowNum, ColNum),
+        format: Option<FormatId>,
+    ) -> PyResult<()> {
+        let segment = self.range_segment((start_row, start_column), end_row)?;
+        let (start_row, end_row) = (segment.row(start_row), segment.row(end_row));
+        let worksheet = worksheet_at(&mut self.workbook, segment.index)?;
+        let default_format = Format::new();
+        let format = match self.layers[segment.index].write_cell(
+            worksheet,
+            &mut self.formats,
+            start_row,
+            start_column,
+            None,
+            format,
+        )? {
+            Some(format) => self.formats.format(format),
+            None => &default_format,
+        };
+        let result = worksheet
+            .merge_range(start_row, start_column, end_row, end_column, "", format)
+            .map(|_| ());
+        result.map_err(|error| {
+            errors::to_py_err(
+                error,
+                Some(worksheet.name().as_str()),
+                Some((start_row, start_column)),
+            )
+        })
+    }
+    /// Sets the format of a row.
+    fn set_row_format_id(&mut self, row: RowNum, format: FormatId) -> PyResult<()> {
+        let (index, row) = self.locate(row)?;
+        let worksheet = worksheet_at(&mut self.workbook, index)?;
+        self.layers[index].set_row(worksheet, &mut self.formats, row, format)
+    }
+
+    /// Sets the format of a range.
+    fn set_range_format_id(
+        &mut self,
+        first: (RowNum, ColNum),
+        last: (RowNum, ColNum),
+        format: FormatId,
+    ) -> PyResult<()> {
+        let segment = self.range_segment(first, last.0)?;
+        let worksheet = worksheet_at(&mut self.workbook, segment.index)?;
+        self.layers[segment.index].set_range(
+            worksheet,
+            &mut self.formats,
+            (segment.row(first.0), first.1),
+            (segment.row(last.0), last.1),
+            format,
+        )
+    }
+
+    /// Adds a format to the cells of a range, or only to the cells written in the range
+    /// if `lazy` is set, keeping the values and other format options of the cells.
+    fn add_format(
+        &mut self,
+        first: (RowNum, ColNum),
+        last: (RowNum, ColNum),
+        format: FormatId,
+        lazy: bool,
+    ) -> PyResult<()> {
+        let segment = self.range_segment(first, last.0)?;
+        let worksheet = worksheet_at(&mut self.workbook, segment.index)?;
+        let layers = &mut self.layers[segment.index];
+        let (first, last) = (
+            (segment.row(first.0), first.1),
+            (segment.row(last.0), last.1),
+        );
+        if lazy {
+            layers.add_to_range(worksheet, &mut self.formats, first, last, format)
+        } else {
+            layers.add_to_cells(worksheet, &mut self.formats, first, last, format)
+        }
+    }
+
+    /// Borrows the worksheet of a segment, with its layers and the workbook settings,
+    /// to write a run of cells.
+    fn sheet_writer(&mut self, segment: &Segment) -> PyResult<SheetWriter<'_>> {
+        let rollover = match segment.is_root {
+            true => self
+                .rollovers
+                .get_mut(self.active_worksheet_index)
+                .and_then(Option::as_mut),
+            false => None,
+        };
+        Ok(SheetWriter {
+            worksheet: worksheet_at(&mut self.workbook, segment.index)?,
+            layers: &mut self.layers[segment.index],
+            formats: &mut self.formats,
+            datetime_formats: &mut self.datetime_formats,
+            string_overflow: &mut self.string_overflow,
+            rollover,
+            tz_policy: self.tz_policy,
+            int_overflow: self.int_overflow,
+            decimal_rounding: self.decimal_rounding,
+            non_finite: &self.non_finite,
+        })
+    }
+
+    /// Writes a value to a cell of the active worksheet, with the format of `format`
+    /// combined with the layers of the cell. This is shared by `write` and the bulk write methods.
+    fn write_value(
+        &mut self,
+        row: RowNum,
+        column: ColNum,
+        value: Option<ValueType>,
+        overrides: &ValueOverrides,
+        format: Option<FormatId>,
+    ) -> PyResult<()> {
+        let segment = self.segment(row)?;
+        self.sheet_writer(&segment)?
+            .write(segment.row(row), column, value, overrides, format)?;
+        self.repeat_header_writes()
+    }
+
+    /// Writes the values of a Python iterable into consecutive cells, starting at
+    /// `(row, column)` and going along the row, or down the column if `down` is set.
+    ///
+    /// The worksheet is looked up once for the run of cells, or once per worksheet
+    /// for a column that continues on a new worksheet.
+    fn write_values(
+        &mut self,
+        (row, column): (RowNum, ColNum),
+        values: &Bound<'_, PyAny>,
+        formats: &FormatIds,
+        overrides: &ValueOverrides,
+        down: bool,
+    ) -> PyResult<()> {
+        let mut values = values.try_iter()?.enumerate().peekable();
+        if !down {
+            let segment = self.segment(row)?;
+            let mut writer = self.sheet_writer(&segment)?;
+            for (index, value) in values {
+                let cell_column = column_offset(column, index);
+                writer.write(
+                    segment.row(row),
+                    cell_column,
+                    value?.extract()?,
+                    overrides,
+                    formats.get(index),
+                )?;
+            }
+            return self.repeat_header_writes();
+        }
+        while let Some(&(first, _)) = values.peek() {
+            let segment = self.segment(row_offset(row, first))?;
+            let mut writer = self.sheet_writer(&segment)?;
+            while let Some((index, value)) =
+                values.next_if(|(index, _)| segment.contains(row_offset(row, *index)))
+            {
+                writer.write(
+                    segment.row(row_offset(row, index)),
+                    column,
+                    value?.extract()?,
+                    overrides,
+                    formats.get(index),
+                )?;
+            }
+        }
+        self.repeat_header_writes()
+    }
+
+    /// Writes an array formula, or a dynamic array formula, with the combined format
+    /// of the first cell of the range.
+    fn write_range_formula(
+        &mut self,
+        first: (RowNum, ColNum),
+        last: (RowNum, ColNum),
+        formula: Formula,
+        format_option: Option<FormatOption>,
+        dynamic: bool,
+    ) -> PyResult<()> {
+        let format = match &format_option {
+            Some(format_option) => Some(self.formats.resolve(format_option)?),
+            None => None,
+        };
+        self.check_header_range(first, last)?;
+        self.write_range_formula_format(first, last, formula.clone(), format, dynamic)?;
+        self.record_header_write(HeaderWrite::RangeFormula {
+            first,
+            last,
+            formula,
+            format,
+            dynamic,
+        })
+    }
+
+    /// Writes an array formula, or a dynamic array formula, with the given format.
+    fn write_range_formula_format(
+        &mut self,
+        first: (RowNum, ColNum),
+        last: (RowNum, ColNum),
+        formula: Formula,
+        format: Option<FormatId>,
+        dynamic: bool,
+    ) -> PyResult<()> {
+        let segment = self.range_segment(first, last.0)?;
+        let (first, last) = (
+            (segment.row(first.0), first.1),
+            (segment.row(last.0), last.1),
+        );
+        let worksheet = worksheet_at(&mut self.workbook, segment.index)?;
+        let default_format = Format::new();
+        let format = match self.layers[segment.index].write_cell(
+            worksheet,
+            &mut self.formats,
+            first.0,
+            first.1,
+            None,
+            format,
+        )? {
+            Some(format) => self.formats.format(format),
+            None => &default_format,
+        };
+        writer::write_array_formula(worksheet, first, last, formula, format, dynamic)
+    }
+}
+
+/// The strings written instead of `True`, `False` and missing values, if any.
+#[derive(Clone)]
+pub struct ValueOverrides {
+    pub true_value: Option<String>,
+    pub false_value: Option<String>,
+    pub missing_value: Option<String>,
+}
+
+/// The worksheet that a run of rows of the active worksheet is written to. Without a
+/// rollover, every row is written to the active worksheet itself.
+struct Segment {
+    index: usize,
+    /// The rows of the active worksheet written to this worksheet.
+    rows: Range<u64>,
+    /// The row index of the first of `rows` in this worksheet.
+    first_row: RowNum,
+    /// Whether this is the worksheet with the rollover, whose header rows are recorded.
+    is_root: bool,
+}
+
+impl Segment {
+    fn contains(&self, row: RowNum) -> bool {
+        self.rows.contains(&u64::from(row))
+    }
+
+    /// Returns the row index in this worksheet of a row of the active worksheet.
+    fn row(&self, row: RowNum) -> RowNum {
+        self.first_row + (u64::from(row) - self.rows.start) as RowNum
+    }
+}
+
+/// Writes values to the cells of a worksheet, borrowing the worksheet, its layers
+/// and the workbook settings once for a run of cells.
+struct SheetWriter<'a> {
+    worksheet: &'a mut Worksheet,
+    layers: &'a mut FormatLayers,
+    formats: &'a mut FormatRegistry,
+    datetime_formats: &'a mut DateTimeFormats,
+    string_overflow: &'a mut StringOverflow,
+    /// The rollover of the worksheet, whose header cells are recorded as they are written.
+    rollover: Option<&'a mut Rollover>,
+    tz_policy: TimezonePolicy,
+    int_overflow: IntOverflowPolicy,
+    decimal_rounding: DecimalRounding,
+    non_finite: &'a NonFinitePolicies,
+}
+
+impl SheetWriter<'_> {
+    /// Writes a value to a cell, with the format of `format` combined with the layers of the cell.
+    fn write(
+        &mut self,
+        row: RowNum,
+        column: ColNum,
+        value: Option<ValueType>,
+        overrides: &ValueOverrides,
+        format: Option<FormatId>,
+    ) -> PyResult<()> {
+        if let Some(rollover) = self.rollover.as_deref_mut() {
+            if row < rollover.header_rows() {
+                rollover.header_writes.push(HeaderWrite::Value {
+                    row,
+                    column,
+                    value: value.clone(),
+                    overrides: overrides.clone(),
+                    format,
+                });
+            }
+        }
+        let worksheet = &mut *self.worksheet;
+        // Resolve the segment formats of rich strings before the cell format borrows the registry.
+        let segment_formats = match &value {
+            Some(ValueType::RichString(value)) => value.format_ids(self.formats)?,
+            _ => Vec::new(),
+        };
+        let value_format = value
+            .as_ref()
+            .and_then(ValueType::datetime_kind)
+            .map(|kind| self.datetime_formats.id(self.formats, kind));
+        let format = self
+            .layers
+            .write_cell(worksheet, self.formats, row, column, value_format, format)?
+            .map(|format| self.formats.format(format));
+
+        if let Some(value) = value {
+            match value {
+                ValueType::String(value) => writer::write_string(
+                    worksheet,
+                    row,
+                    column,
+                    value,
+                    format,
+                    self.string_overflow,
+                ),
+                ValueType::Bool(value) => writer::write_boolean(
+                    worksheet,
+                    row,
+                    column,
+                    value,
+                    overrides.true_value.as_deref(),
+                    overrides.false_value.as_deref(),
+                    format,
+                ),
+                ValueType::Int(value) => {
+                    writer::write_integer(worksheet, row, column, value, format, self.int_overflow)
+                }
+                ValueType::Decimal(value) => {
+                    let value = value.to_f64(self.decimal_rounding)?;
+                    writer::write_float(
+                        worksheet,
+                        row,
+                        column,
+                        value,
+                        format,
+                        self.non_finite,
+                        overrides.missing_value.as_deref(),
+                    )
+                }
+                ValueType::Float(value) => writer::write_float(
+                    worksheet,
+                    row,
+                    column,
+                    value,
+                    format,
+                    self.non_finite,
+                    overrides.missing_value.as_deref(),
+                ),
+                ValueType::DateTime(value) => writer::write_datetime(
+                    worksheet,
+                    row,
+                    column,
+                    value.to_excel(self.tz_policy),
+                    format,
+                ),
+                ValueType::Date(value) => {
+                    writer::write_datetime(worksheet, row, column, value.to_excel(), format)
+                }
+                ValueType::Time(value) => writer::write_datetime(
+                    worksheet,
+                    row,
+                    column,
+                    value.to_excel(self.tz_policy),
+                    format,
+                ),
+                ValueType::TimeDelta(value) => {
+                    writer::write_datetime(worksheet, row, column, value.to_excel(), format)
+                }
+                ValueType::Missing(_) => writer::write_null(
+                    worksheet,
+                    row,
+                    column,
+                    overrides.missing_value.as_deref(),
+                    format,
+                ),
+                ValueType::Error(value) => {
+                    writer::write_error(worksheet, row, column, value.value(), format)
+                }
+                ValueType::RichString(value) => {
+                    let default_format = Format::new();
+                    let segments: Vec<(&Format, &str)> = segment_formats
+                        .into_iter()
+                        .map(|id| id.map_or(&default_format, |id| self.formats.format(id)))
+                        .zip(value.texts())
+                        .collect();
+                    writer::write_rich_string(worksheet, row, column, &segments, format)
+                }
+            }
+        } else {
+            writer::write_null(
+                worksheet,
+                row,
+                column,
+                overrides.missing_value.as_deref(),
+                format,
+            )
+        }
+    }
+}
+
+/// Returns the row `index` rows below `row`. The row saturates instead of wrapping
+/// around, so that it is rejected by the worksheet limits.
+fn row_offset(row: RowNum, index: usize) -> RowNum {
+    RowNum::try_from(index).map_or(RowNum::MAX, |index| row.saturating_add(index))
+}
+
+/// Returns the column `index` columns right of `column`, saturating like `row_offset`.
+fn column_offset(column: ColNum, index: usize) -> ColNum {
+    ColNum::try_from(index).map_or(ColNum::MAX, |index| column.saturating_add(index))
+}
+
+/// Returns a `Formula` with the given cached result, if any.
+fn new_formula(formula: String, value: Option<FormulaResult>) -> Formula {
+    let formula = Formula::new(uppercase_function_names(&formula));
+    match value {
+        Some(value) => formula.set_result(value.to_result_string()),
+        None => formula,
+    }
+}
+
+/// Returns the formula with its function names in upper case.
+///
+/// `rust_xlsxwriter` only prefixes upper-case names of newer Excel functions with `_xlfn.`,
+/// while Excel accepts them in any case, e.g. `=xlookup(...)`. Names starting with `_`,
+/// such as the already prefixed `_xlfn.xlookup`, string literals, quoted sheet names and
+/// structured references are kept as they are.
+fn uppercase_function_names(formula: &str) -> String {
+    let mut result = String::with_capacity(formula.len());
+    let mut quote = None;
+    let mut token = String::new();
+    for char in formula.chars() {
+        if let Some(end) = quote {
+            result.push(char);
+            if char == end {
+                quote = None;
+            }
+            continue;
+        }
+        if char.is_ascii_alphanumeric() || char == '.' || char == '_' {
+            token.push(char);
+            continue;
+        }
+        if char == '(' && token.starts_with(|first: char| first.is_ascii_alphabetic()) {
+            token.make_ascii_uppercase();
+        }
+        result.push_str(&token);
+        token.clear();
+        result.push(char);
+        quote = match char {
+            '"' => Some('"'),
+            '\'' => Some('\''),
+            '[' => Some(']'),
+            _ => None,
+        };
+    }
+    result.push_str(&token);
+    result
+}
+
+/// Returns the worksheet at `index`, usually the `active_worksheet_index`.
+///
+/// This borrows only the `Workbook`, so that the other `ExcelWorkbook`
+/// fields can be used while writing to the worksheet.
+fn worksheet_at(workbook: &mut Workbook, index: usize) -> PyResult<&mut Worksheet> {
+    workbook
+        .worksheet_from_index(index)
+        .map_err(|error| errors::to_py_err(error, None, None))
+}
 
 impl Default for ExcelWorkbook {
     fn default() -> Self {
-        Self::new(false)
+        Self::new(
+            false,
+            StringOverflowPolicy::Truncate,
+            None,
+            true,
+            "yyyy-mm-dd".to_string(),
+            "yyyy-mm-dd hh:mm:ss".to_string(),
+            "hh:mm:ss".to_string(),
+            "[h]:mm:ss".to_string(),
+            TimezonePolicy::Raise,
+            IntOverflowPolicy::Text,
+            None,
+            DecimalRoundingMode::HALF_EVEN,
+            NonFinitePolicy::Missing,
+            NonFinitePolicy::Raise,
+            false,
+            None,
+        )
+        .expect("the default workbook options are valid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn function_names_are_upper_cased() {
+        assert_eq!(
+            uppercase_function_names("=xlookup(a1,Sheet1!a:a,b:b)+sum(1)"),
+            "=XLOOKUP(a1,Sheet1!a:a,b:b)+SUM(1)"
+        );
+        assert_eq!(
+            uppercase_function_names("=concat(\"f(\",'my sheet(1)'!a1,t[x(y)])"),
+            "=CONCAT(\"f(\",'my sheet(1)'!a1,t[x(y)])"
+        );
+        assert_eq!(
+            uppercase_function_names("=_xlfn.xlookup(1,a:a,b:b)"),
+            "=_xlfn.xlookup(1,a:a,b:b)"
+        );
+    }
+
+    fn rollover_workbook() -> ExcelWorkbook {
+        let mut workbook = ExcelWorkbook::default();
+        workbook
+            .add_worksheet(
+                Some("Data".to_string()),
+                SheetOverflowPolicy::NewSheet,
+                Some(1),
+            )
+            .unwrap();
+        workbook
+    }
+
+    fn format(options: ExcelFormat) -> Option<FormatOption> {
+        Some(FormatOption::Format(options))
+    }
+
+    /// Saves the workbook and returns the XML of its worksheets.
+    fn saved_worksheets(workbook: &mut ExcelWorkbook, name: &str) -> Vec<String> {
+        let path = std::env::temp_dir().join(format!("pyaccelsx-{name}.xlsx"));
+        let path = path.to_string_lossy().into_owned();
+        workbook.save(path.clone()).unwrap();
+        let count = workbook.workbook.worksheets().len();
+        let worksheets = Python::with_gil(|py| {
+            let archive = py
+                .import("zipfile")
+                .unwrap()
+                .call_method1("ZipFile", (&path,))
+                .unwrap();
+            (1..=count)
+                .map(|sheet| {
+                    archive
+                        .call_method1("read", (format!("xl/worksheets/sheet{sheet}.xml"),))
+                        .unwrap()
+                        .call_method0("decode")
+                        .unwrap()
+                        .extract()
+                        .unwrap()
+                })
+                .collect()
+        });
+        std::fs::remove_file(path).unwrap();
+        worksheets
+    }
+
+    /// Returns the parts of a worksheet XML repeated on its continuation worksheets:
+    /// the first row, the merged ranges and the hyperlinks.
+    fn header_parts(xml: &str) -> Vec<&str> {
+        [
+            ("<row r=\"1\"", "</row>"),
+            ("<mergeCells", "</mergeCells>"),
+            ("<hyperlinks", "</hyperlinks>"),
+        ]
+        .iter()
+        .map(|(start, end)| {
+            let first = xml.find(start).expect("the part is written");
+            let last = first + xml[first..].find(end).unwrap() + end.len();
+            &xml[first..last]
+        })
+        .collect()
+    }
+
+    #[test]
+    fn header_writes_are_repeated_on_continuation_worksheets() {
+        pyo3::prepare_freethreaded_python();
+        let mut workbook = rollover_workbook();
+        let tenths = ExcelFormat::with_num_format("0.0".to_string());
+        let hundredths = ExcelFormat::with_num_format("0.00".to_string());
+        workbook
+            .set_row_format(0, FormatOption::Format(tenths.clone()))
+            .unwrap();
+        workbook.write_blank(0, 0, format(hundredths)).unwrap();
+        workbook
+            .write(0, 1, Some(ValueType::Float(1.0)), None, None, None, None)
+            .unwrap();
+        workbook
+            .write(
+                ROW_MAX,
+                0,
+                Some(ValueType::Float(2.0)),
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+
+        // Written after the continuation worksheet was added.
+        let segments = Python::with_gil(|py| {
+            py.eval(c"[('a', None), ('b', None)]", None, None)
+                .unwrap()
+                .extract()
+                .unwrap()
+        });
+        workbook.write_rich_string(0, 2, segments, None).unwrap();
+        workbook
+            .write(
+                0,
+                0,
+                Some(ValueType::Float(3.0)),
+                None,
+                None,
+                None,
+                format(tenths),
+            )
+            .unwrap();
+        workbook
+            .write_url(0, 3, "https://example.com".to_string(), None, None, None)
+            .unwrap();
+        workbook
+            .write_formula(0, 4, "=xlookup(1,A:A,B:B)".to_string(), None, None)
+            .unwrap();
+        workbook.merge_range(0, 5, 0, 6, None).unwrap();
+        let thousandths = ExcelFormat::with_num_format("0.000".to_string());
+        workbook
+            .set_range_format(0, 7, 3, 7, FormatOption::Format(thousandths))
+            .unwrap();
+        workbook
+            .write(0, 7, Some(ValueType::Float(4.0)), None, None, None, None)
+            .unwrap();
+        let thin = BorderOption::Style("thin".to_string());
+        workbook
+            .draw_border(0, 8, 2, 9, thin.clone(), None, Some(thin))
+            .unwrap();
+
+        let worksheets = saved_worksheets(&mut workbook, "header-writes");
+        assert_eq!(header_parts(&worksheets[1]), header_parts(&worksheets[0]));
+    }
+
+    #[test]
+    fn header_rows_are_fixed_by_the_first_continuation_worksheet() {
+        let mut workbook = ExcelWorkbook::default();
+        workbook
+            .add_worksheet(
+                Some("Data".to_string()),
+                SheetOverflowPolicy::NewSheet,
+                None,
+            )
+            .unwrap();
+        workbook.freeze_panes(1, 0).unwrap();
+        workbook
+            .write(
+                ROW_MAX,
+                0,
+                Some(ValueType::Float(1.0)),
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        workbook.freeze_panes(3, 0).unwrap();
+        // The last row of "Data (2)", below its single header row.
+        workbook
+            .write(
+                2 * ROW_MAX - 2,
+                0,
+                Some(ValueType::Float(2.0)),
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        assert_eq!(workbook.workbook.worksheets().len(), 2);
+    }
+
+    #[test]
+    fn merges_crossing_the_last_header_row_are_rejected() {
+        pyo3::prepare_freethreaded_python();
+        let mut workbook = rollover_workbook();
+        let error = workbook.merge_range(0, 0, 1, 0, None).unwrap_err();
+        Python::with_gil(|py| {
+            assert!(error.is_instance_of::<errors::ParameterError>(py));
+            assert!(error.to_string().contains("last header row"));
+        });
+        assert!(workbook.merge_range(1, 0, 2, 0, None).is_ok());
+    }
+
+    #[test]
+    fn ranges_past_the_row_limit_are_written_to_continuation_worksheets() {
+        let mut workbook = rollover_workbook();
+        workbook
+            .merge_range(ROW_MAX + 5, 0, ROW_MAX + 6, 2, None)
+            .unwrap();
+        let style = BorderOption::Style("thin".to_string());
+        workbook
+            .draw_border(ROW_MAX + 8, 0, ROW_MAX + 10, 2, style, None, None)
+            .unwrap();
+        let names: Vec<_> = workbook
+            .workbook
+            .worksheets()
+            .iter()
+            .map(|worksheet| worksheet.name())
+            .collect();
+        assert_eq!(names, ["Data", "Data (2)"]);
+        assert_eq!(workbook.active_worksheet_index, 0);
+    }
+
+    #[test]
+    fn invalid_borders_do_not_add_continuation_worksheets() {
+        pyo3::prepare_freethreaded_python();
+        let mut workbook = rollover_workbook();
+        let style = BorderOption::Style("thikc".to_string());
+        assert!(workbook
+            .draw_border(ROW_MAX + 1, 0, ROW_MAX + 2, 2, style, None, None)
+            .is_err());
+        assert_eq!(workbook.workbook.worksheets().len(), 1);
+    }
+
+    #[test]
+    fn ranges_crossing_the_row_limit_are_rejected() {
+        pyo3::prepare_freethreaded_python();
+        let mut workbook = rollover_workbook();
+        let error = workbook
+            .merge_range(ROW_MAX - 1, 0, ROW_MAX, 2, None)
+            .unwrap_err();
+        Python::with_gil(|py| {
+            assert!(error.is_instance_of::<errors::ParameterError>(py));
+            assert!(error.to_string().contains("crosses the last row"));
+        });
+        let style = BorderOption::Style("thin".to_string());
+        assert!(workbook
+            .draw_border(ROW_MAX, 0, ROW_MAX - 2, 2, style, None, None)
+            .is_err());
+        // The same range fits in a worksheet without a rollover, up to Excel's own limit.
+        workbook
+            .add_worksheet(None, SheetOverflowPolicy::Error, None)
+            .unwrap();
+        assert!(workbook
+            .merge_range(ROW_MAX - 2, 0, ROW_MAX - 1, 2, None)
+            .is_ok());
     }
 }
@@ -1,30 +1,248 @@
+use std::collections::HashSet;
+use std::ffi::CString;
+
+use pyo3::exceptions::{PyUserWarning, PyValueError};
 use pyo3::prelude::*;
-use rust_xlsxwriter::{ColNum, RowNum, Worksheet};
+use rust_xlsxwriter::{
+    row_col_to_cell, ColNum, ExcelDateTime, Format, Formula, Note, RowNum, Url, Worksheet,
+    XlsxError,
+};
 
-use crate::format::{self, ExcelFormat};
+use crate::error_value::{NonFinitePolicies, NonFinitePolicy};
+use crate::errors;
+use crate::layers::COL_MAX;
+use crate::number::{IntOverflowPolicy, IntValue};
 
+/// The maximum string length in a cell, counted in UTF-16 code units like Excel does.
 const MAX_LENGTH: usize = 32767;
+/// The maximum note length, which leaves room for the note author prefix.
+const MAX_NOTE_LENGTH: usize = MAX_LENGTH - 54;
+/// The maximum number of hyperlinks in a worksheet.
+const MAX_HYPERLINKS: usize = 65_530;
+
+/// Maps the result of a cell write into a `PyResult`, attaching the worksheet
+/// name and the cell position to the raised exception.
+fn check_cell(
+    result: Result<(), XlsxError>,
+    worksheet: &Worksheet,
+    row: RowNum,
+    column: ColNum,
+) -> PyResult<()> {
+    result.map_err(|error| {
+        errors::to_py_err(error, Some(worksheet.name().as_str()), Some((row, column)))
+    })
+}
+
+/// The policy applied to strings longer than Excel's cell limit.
+///
+/// Accepted values from Python are `"truncate"`, `"error"`,
+/// `"spill_to_next_cells"` and `"attach_as_comment"`.
+#[derive(Clone, Copy, PartialEq)]
+pub enum StringOverflowPolicy {
+    /// Cut the string at the limit, optionally ending it with a suffix.
+    Truncate,
+    /// Raise a `StringLengthError`.
+    Error,
+    /// Write the remainder of the string into the cells to the right.
+    SpillToNextCells,
+    /// Write the truncated string and attach the full string as a cell note.
+    AttachAsComment,
+}
+
+impl StringOverflowPolicy {
+    fn name(&self) -> &'static str {
+        match self {
+            StringOverflowPolicy::Truncate => "truncate",
+            StringOverflowPolicy::Error => "error",
+            StringOverflowPolicy::SpillToNextCells => "spill_to_next_cells",
+            StringOverflowPolicy::AttachAsComment => "attach_as_comment",
+        }
+    }
+}
+
+impl<'py> FromPyObject<'py> for StringOverflowPolicy {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        match ob.extract::<&str>()? {
+            "truncate" => Ok(StringOverflowPolicy::Truncate),
+            "error" => Ok(StringOverflowPolicy::Error),
+            "spill_to_next_cells" => Ok(StringOverflowPolicy::SpillToNextCells),
+            "attach_as_comment" => Ok(StringOverflowPolicy::AttachAsComment),
+            other => Err(PyValueError::new_err(format!(
+                "Unknown string_overflow '{other}'. Expected one of: 'truncate', 'error', 'spill_to_next_cells', 'attach_as_comment'."
+            ))),
+        }
+    }
+}
+
+/// A record of a string that exceeded Excel's cell limit.
+/// Lengths are counted in UTF-16 code units, the same way Excel counts them.
+#[pyclass(get_all, frozen)]
+#[derive(Clone)]
+pub struct StringOverflowRecord {
+    sheet: String,
+    cell: String,
+    row: RowNum,
+    column: ColNum,
+    original_length: usize,
+    written_length: usize,
+    policy: &'static str,
+}
+
+/// The configured overflow policy of a workbook, along with the records of
+/// every overflowing string written so far.
+pub struct StringOverflow {
+    pub policy: StringOverflowPolicy,
+    pub truncate_suffix: Option<String>,
+    pub report: Vec<StringOverflowRecord>,
+}
+
+impl StringOverflow {
+    /// Creates the overflow policy of a workbook. Raises `ParameterError` if the
+    /// suffix alone is longer than Excel's limit, since no string would fit before it.
+    pub fn new(
+        policy: StringOverflowPolicy,
+        truncate_suffix: Option<String>,
+    ) -> PyResult<StringOverflow> {
+        if let Some(suffix) = &truncate_suffix {
+            let suffix_length = suffix.encode_utf16().count();
+            if suffix_length > MAX_LENGTH {
+                return Err(errors::new_err::<errors::ParameterError>(
+                    format!("truncate_suffix is {suffix_length} characters long, longer than Excel's limit of {MAX_LENGTH} characters."),
+                    None,
+                    None,
+                ));
+            }
+        }
+        Ok(StringOverflow {
+            policy,
+            truncate_suffix,
+            report: Vec::new(),
+        })
+    }
+}
+
+/// Returns the byte index at which `value` holds at most `limit` UTF-16 code units,
+/// always on a character boundary.
+fn utf16_boundary(value: &str, limit: usize) -> usize {
+    let mut units = 0;
+    for (index, character) in value.char_indices() {
+        units += character.len_utf16();
+        if units > limit {
+            return index;
+        }
+    }
+    value.len()
+}
+
+/// Splits `value` into chunks of at most `limit` UTF-16 code units.
+fn utf16_chunks(value: &str, limit: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = value;
+    while !rest.is_empty() {
+        let boundary = utf16_boundary(rest, limit);
+        chunks.push(&rest[..boundary]);
+        rest = &rest[boundary..];
+    }
+    chunks
+}
+
+/// Returns `value` cut to Excel's limit, ending with `suffix` if one is given.
+fn truncate(value: &str, suffix: Option<&str>) -> String {
+    let suffix = suffix.unwrap_or("");
+    let suffix_length: usize = suffix.encode_utf16().count();
+    let boundary = utf16_boundary(value, MAX_LENGTH.saturating_sub(suffix_length));
+    let mut truncated = String::with_capacity(boundary + suffix.len());
+    truncated.push_str(&value[..boundary]);
+    truncated.push_str(suffix);
+    truncated
+}
 
+fn write_string_chunk(
+    worksheet: &mut Worksheet,
+    row: RowNum,
+    column: ColNum,
+    value: &str,
+    format: Option<&Format>,
+) -> PyResult<()> {
+    let result = match format {
+        None => worksheet.write_string(row, column, value).map(|_| ()),
+        Some(format) => worksheet
+            .write_string_with_format(row, column, value, format)
+            .map(|_| ()),
+    };
+    check_cell(result, worksheet, row, column)
+}
+
+/// Writes a string into a cell, applying the workbook string overflow policy
+/// if the string exceeds Excel's limit of 32,767 characters.
 pub fn write_string(
     worksheet: &mut Worksheet,
     row: RowNum,
     column: ColNum,
     value: String,
-    format_option: Option<ExcelFormat>,
+    format: Option<&Format>,
+    overflow: &mut StringOverflow,
 ) -> PyResult<()> {
-    let mut value = value;
-    if value.len() > MAX_LENGTH {
-        // Truncate the string
-        value.truncate(MAX_LENGTH);
-    }
-    if format_option.is_none() {
-        worksheet.write_string(row, column, value).unwrap();
+    // A string cannot hold more UTF-16 code units than it has bytes.
+    let original_length = if value.len() > MAX_LENGTH {
+        value.encode_utf16().count()
     } else {
-        let format = format::create_format(format_option.unwrap());
-        worksheet
-            .write_string_with_format(row, column, value, &format)
-            .unwrap();
+        0
+    };
+    if original_length <= MAX_LENGTH {
+        return write_string_chunk(worksheet, row, column, &value, format);
     }
+
+    let written_length = match overflow.policy {
+        StringOverflowPolicy::Error => {
+            return Err(errors::to_py_err(
+                XlsxError::MaxStringLengthExceeded,
+                Some(worksheet.name().as_str()),
+                Some((row, column)),
+            ));
+        }
+        StringOverflowPolicy::Truncate => {
+            let truncated = truncate(&value, overflow.truncate_suffix.as_deref());
+            write_string_chunk(worksheet, row, column, &truncated, format)?;
+            truncated.encode_utf16().count()
+        }
+        StringOverflowPolicy::SpillToNextCells => {
+            let chunks = utf16_chunks(&value, MAX_LENGTH);
+            // Check the last column first, so that a spill that does not fit writes nothing.
+            if column as usize + chunks.len() > COL_MAX as usize {
+                return Err(errors::to_py_err(
+                    XlsxError::RowColumnLimitError,
+                    Some(worksheet.name().as_str()),
+                    Some((row, column)),
+                ));
+            }
+            for (offset, chunk) in chunks.into_iter().enumerate() {
+                write_string_chunk(worksheet, row, column + offset as ColNum, chunk, format)?;
+            }
+            original_length
+        }
+        StringOverflowPolicy::AttachAsComment => {
+            let truncated = truncate(&value, overflow.truncate_suffix.as_deref());
+            write_string_chunk(worksheet, row, column, &truncated, format)?;
+            // Notes have a slightly lower limit than cells, anything beyond it is dropped.
+            let note_text = &value[..utf16_boundary(&value, MAX_NOTE_LENGTH)];
+            let result = worksheet
+                .insert_note(row, column, &Note::new(note_text))
+                .map(|_| ());
+            check_cell(result, worksheet, row, column)?;
+            truncated.encode_utf16().count()
+        }
+    };
+
+    overflow.report.push(StringOverflowRecord {
+        sheet: worksheet.name(),
+        cell: row_col_to_cell(row, column),
+        row,
+        column,
+        original_length,
+        written_length,
+        policy: overflow.policy.name(),
+    });
     Ok(())
 }
 
@@ -33,71 +251,330 @@ pub fn write_number(
     row: RowNum,
     column: ColNum,
     value: f64,
-    format_option: Option<ExcelFormat>,
+    format: Option<&Format>,
+) -> PyResult<()> {
+    let result = match format {
+        None => worksheet.write_number(row, column, value).map(|_| ()),
+        Some(format) => worksheet
+            .write_number_with_format(row, column, value, format)
+            .map(|_| ()),
+    };
+    check_cell(result, worksheet, row, column)
+}
+
+/// Writes a number, applying the workbook NaN and infinity policies if Excel cannot store it.
+/// `missing_value` is the string written instead of missing values, if any.
+pub fn write_float(
+    worksheet: &mut Worksheet,
+    row: RowNum,
+    column: ColNum,
+    value: f64,
+    format: Option<&Format>,
+    policies: &NonFinitePolicies,
+    missing_value: Option<&str>,
+) -> PyResult<()> {
+    match policies.get(value) {
+        None => write_number(worksheet, row, column, value, format),
+        Some(NonFinitePolicy::Raise) => Err(errors::new_err::<errors::ParameterError>(
+            format!("{value} cannot be written, since Excel only stores finite numbers."),
+            Some(worksheet.name().as_str()),
+            Some((row, column)),
+        )),
+        Some(NonFinitePolicy::Missing) => write_null(worksheet, row, column, missing_value, format),
+        Some(NonFinitePolicy::Blank) => write_null(worksheet, row, column, None, format),
+        Some(NonFinitePolicy::Error(error)) => {
+            write_error(worksheet, row, column, error.value(), format)
+        }
+        Some(NonFinitePolicy::Text(text)) => {
+            write_string_chunk(worksheet, row, column, text, format)
+        }
+    }
+}
+
+/// Writes an Excel error value, such as `#N/A`, as a formula returning the error.
+pub fn write_error(
+    worksheet: &mut Worksheet,
+    row: RowNum,
+    column: ColNum,
+    error: &str,
+    format: Option<&Format>,
+) -> PyResult<()> {
+    let formula = Formula::new(error).set_result(error);
+    write_formula(worksheet, row, column, formula, format)
+}
+
+/// Writes an integer, applying the workbook integer overflow policy if Excel
+/// cannot store it exactly.
+pub fn write_integer(
+    worksheet: &mut Worksheet,
+    row: RowNum,
+    column: ColNum,
+    value: IntValue,
+    format: Option<&Format>,
+    policy: IntOverflowPolicy,
+) -> PyResult<()> {
+    if let Some(value) = value.to_exact_f64() {
+        return write_number(worksheet, row, column, value, format);
+    }
+
+    let digits = value.to_text();
+    match policy {
+        IntOverflowPolicy::Text => write_string_chunk(worksheet, row, column, &digits, format),
+        IntOverflowPolicy::Number => {
+            // Every decimal integer parses, at worst to infinity.
+            let number: f64 = digits.parse().unwrap_or(f64::INFINITY);
+            let message = format!(
+                "Integer {digits} was written as the number {number}, since Excel cannot store it exactly (sheet '{}', cell {}).",
+                worksheet.name(),
+                row_col_to_cell(row, column),
+            );
+            Python::with_gil(|py| {
+                let category = py.get_type::<PyUserWarning>();
+                PyErr::warn(py, &category, &CString::new(message)?, 1)
+            })?;
+            write_number(worksheet, row, column, number, format)
+        }
+        IntOverflowPolicy::Error => Err(errors::new_err::<errors::ParameterError>(
+            format!("Integer {digits} is beyond 2^53 and cannot be stored exactly by Excel."),
+            Some(worksheet.name().as_str()),
+            Some((row, column)),
+        )),
+    }
+}
+
+pub fn write_formula(
+    worksheet: &mut Worksheet,
+    row: RowNum,
+    column: ColNum,
+    formula: Formula,
+    format: Option<&Format>,
 ) -> PyResult<()> {
-    if format_option.is_none() {
-        worksheet.write_number(row, column, value).unwrap();
+    let result = match format {
+        None => worksheet.write_formula(row, column, formula).map(|_| ()),
+        Some(format) => worksheet
+            .write_formula_with_format(row, column, formula, format)
+            .map(|_| ()),
+    };
+    check_cell(result, worksheet, row, column)
+}
+
+/// Writes an array formula into a range, or a dynamic array formula if `dynamic` is set.
+pub fn write_array_formula(
+    worksheet: &mut Worksheet,
+    (first_row, first_column): (RowNum, ColNum),
+    (last_row, last_column): (RowNum, ColNum),
+    formula: Formula,
+    format: &Format,
+    dynamic: bool,
+) -> PyResult<()> {
+    let result = if dynamic {
+        worksheet.write_dynamic_array_formula_with_format(
+            first_row,
+            first_column,
+            last_row,
+            last_column,
+            formula,
+            format,
+        )
     } else {
-        let format = format::create_format(format_option.unwrap());
-        worksheet
-            .write_number_with_format(row, column, value, &format)
-            .unwrap();
+        worksheet.write_array_formula_with_format(
+            first_row,
+            first_column,
+            last_row,
+            last_column,
+            formula,
+            format,
+        )
     }
+    .map(|_| ());
+    check_cell(result, worksheet, first_row, first_column)
+}
+
+/// Writes a string made of segments with different font formats into a cell.
+pub fn write_rich_string(
+    worksheet: &mut Worksheet,
+    row: RowNum,
+    column: ColNum,
+    segments: &[(&Format, &str)],
+    format: Option<&Format>,
+) -> PyResult<()> {
+    let result = match format {
+        None => worksheet
+            .write_rich_string(row, column, segments)
+            .map(|_| ()),
+        Some(format) => worksheet
+            .write_rich_string_with_format(row, column, segments, format)
+            .map(|_| ()),
+    };
+    check_cell(result, worksheet, row, column)
+}
+
+/// Writes a hyperlink into a cell, keeping track of the cells of the worksheet that hold one.
+/// Without a format, the cell gets Excel's "Hyperlink" cell style.
+pub fn write_url(
+    worksheet: &mut Worksheet,
+    row: RowNum,
+    column: ColNum,
+    url: Url,
+    format: Option<&Format>,
+    hyperlinks: &mut HashSet<(RowNum, ColNum)>,
+) -> PyResult<()> {
+    // Writing over an existing hyperlink replaces it, so it does not count towards the limit.
+    if hyperlinks.len() >= MAX_HYPERLINKS && !hyperlinks.contains(&(row, column)) {
+        return Err(errors::new_err::<errors::HyperlinkError>(
+            format!("Worksheet exceeds Excel's limit of {MAX_HYPERLINKS} hyperlinks."),
+            Some(worksheet.name().as_str()),
+            Some((row, column)),
+        ));
+    }
+    let result = match format {
+        None => worksheet.write_url(row, column, url).map(|_| ()),
+        Some(format) => worksheet
+            .write_url_with_format(row, column, url, format)
+            .map(|_| ()),
+    };
+    check_cell(result, worksheet, row, column)?;
+    hyperlinks.insert((row, column));
     Ok(())
 }
 
+/// Writes a date or time converted to an Excel serial date, or raises the conversion error.
+pub fn write_datetime(
+    worksheet: &mut Worksheet,
+    row: RowNum,
+    column: ColNum,
+    datetime: Result<ExcelDateTime, XlsxError>,
+    format: Option<&Format>,
+) -> PyResult<()> {
+    let result = datetime.and_then(|datetime| match format {
+        None => worksheet.write_datetime(row, column, datetime).map(|_| ()),
+        Some(format) => worksheet
+            .write_datetime_with_format(row, column, datetime, format)
+            .map(|_| ()),
+    });
+    check_cell(result, worksheet, row, column)
+}
+
 pub fn write_boolean(
     worksheet: &mut Worksheet,
     row: RowNum,
     column: ColNum,
     value: bool,
-    override_true_value: Option<String>,
-    override_false_value: Option<String>,
-    format_option: Option<ExcelFormat>,
+    override_true_value: Option<&str>,
+    override_false_value: Option<&str>,
+    format: Option<&Format>,
 ) -> PyResult<()> {
     let override_value = if value {
         override_true_value
     } else {
         override_false_value
     };
-    if format_option.is_none() {
-        match override_value {
-            Some(override_value) => worksheet.write_string(row, column, override_value).unwrap(),
-            None => worksheet.write_boolean(row, column, value).unwrap(),
-        };
-    } else {
-        let format = format::create_format(format_option.unwrap());
-        match override_value {
-            Some(override_value) => worksheet
-                .write_string_with_format(row, column, override_value, &format)
-                .unwrap(),
-            None => worksheet
-                .write_boolean_with_format(row, column, value, &format)
-                .unwrap(),
-        };
-    }
-    Ok(())
+    let result = match format {
+        None => match override_value {
+            Some(override_value) => worksheet.write_string(row, column, override_value),
+            None => worksheet.write_boolean(row, column, value),
+        }
+        .map(|_| ()),
+        Some(format) => match override_value {
+            Some(override_value) => {
+                worksheet.write_string_with_format(row, column, override_value, format)
+            }
+            None => worksheet.write_boolean_with_format(row, column, value, format),
+        }
+        .map(|_| ()),
+    };
+    check_cell(result, worksheet, row, column)
 }
 
 pub fn write_null(
     worksheet: &mut Worksheet,
     row: RowNum,
     column: ColNum,
-    override_value: Option<String>,
-    format_option: Option<ExcelFormat>,
+    override_value: Option<&str>,
+    format: Option<&Format>,
 ) -> PyResult<()> {
-    if format_option.is_none() {
-        if let Some(override_value) = override_value {
-            worksheet.write_string(row, column, override_value).unwrap();
-        }
-    } else {
-        let format = format::create_format(format_option.unwrap());
-        match override_value {
+    let result = match format {
+        None => match override_value {
             Some(override_value) => worksheet
-                .write_string_with_format(row, column, override_value, &format)
-                .unwrap(),
-            None => worksheet.write_blank(row, column, &format).unwrap(),
+                .write_string(row, column, override_value)
+                .map(|_| ()),
+            None => Ok(()),
+        },
+        Some(format) => match override_value {
+            Some(override_value) => {
+                worksheet.write_string_with_format(row, column, override_value, format)
+            }
+            None => worksheet.write_blank(row, column, format),
+        }
+        .map(|_| ()),
+    };
+    check_cell(result, worksheet, row, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn utf16_boundary_counts_utf16_units() {
+        // "😀" is a single character but two UTF-16 code units, and four bytes.
+        assert_eq!(utf16_boundary("ab😀c", 2), 2);
+        assert_eq!(utf16_boundary("ab😀c", 3), 2);
+        assert_eq!(utf16_boundary("ab😀c", 4), 6);
+        assert_eq!(utf16_boundary("abc", 10), 3);
+    }
+
+    #[test]
+    fn truncate_fits_the_limit() {
+        let value = "é".repeat(MAX_LENGTH + 10);
+        let truncated = truncate(&value, None);
+        assert_eq!(truncated.encode_utf16().count(), MAX_LENGTH);
+
+        let truncated = truncate(&value, Some("…"));
+        assert_eq!(truncated.encode_utf16().count(), MAX_LENGTH);
+        assert!(truncated.ends_with("é…"));
+    }
+
+    #[test]
+    fn truncate_never_splits_a_surrogate_pair() {
+        let value = format!("{}{}", "a".repeat(MAX_LENGTH - 1), "😀".repeat(2));
+        let truncated = truncate(&value, None);
+        assert_eq!(truncated.encode_utf16().count(), MAX_LENGTH - 1);
+    }
+
+    #[test]
+    fn utf16_chunks_spill_the_whole_string() {
+        let value = "😀".repeat(MAX_LENGTH);
+        let chunks = utf16_chunks(&value, MAX_LENGTH);
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks
+            .iter()
+            .all(|chunk| chunk.encode_utf16().count() <= MAX_LENGTH));
+        assert_eq!(chunks.concat(), value);
+    }
+
+    #[test]
+    fn truncate_suffix_longer_than_the_limit_is_rejected() {
+        let suffix = "a".repeat(MAX_LENGTH + 1);
+        pyo3::prepare_freethreaded_python();
+        assert!(StringOverflow::new(StringOverflowPolicy::Truncate, Some(suffix)).is_err());
+        let suffix = "a".repeat(MAX_LENGTH);
+        assert!(StringOverflow::new(StringOverflowPolicy::Truncate, Some(suffix)).is_ok());
+    }
+
+    #[test]
+    fn nan_follows_the_nan_policy() {
+        pyo3::prepare_freethreaded_python();
+        let mut worksheet = Worksheet::new();
+        let policies = |nan| NonFinitePolicies {
+            nan,
+            inf: NonFinitePolicy::Raise,
         };
+        let missing = policies(NonFinitePolicy::Missing);
+        assert!(write_float(&mut worksheet, 0, 0, f64::NAN, None, &missing, Some("-")).is_ok());
+        assert!(write_float(&mut worksheet, 0, 1, f64::INFINITY, None, &missing, None).is_err());
+        let raise = policies(NonFinitePolicy::Raise);
+        assert!(write_float(&mut worksheet, 0, 2, f64::NAN, None, &raise, None).is_err());
+        assert!(write_float(&mut worksheet, 0, 3, 1.5, None, &raise, None).is_ok());
     }
-    Ok(())
 }